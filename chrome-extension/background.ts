@@ -126,6 +126,17 @@ chrome.contextMenus.onClicked.addListener((item, tab) => {
               .replace(/,-$/g, "")
           }
 
+          function getContext(selection, wordCount) {
+            return [
+              ...getPrecedingWords(selection, wordCount),
+              selection.toString(),
+              ...getFollowingWords(selection, wordCount)
+            ]
+              .join(" ")
+              .replace(/\s+/g, " ")
+              .trim()
+          }
+
           const selection = window.getSelection()
           if (selection?.rangeCount > 0) {
             const surroundingText = getSurroundingText(selection, 4)
@@ -133,10 +144,11 @@ chrome.contextMenus.onClicked.addListener((item, tab) => {
             url.hash = `:~:text=${surroundingText}`
             return {
               text: selection.toString().trim(),
-              highlightUrl: url.href
+              highlightUrl: url.href,
+              context: getContext(selection, 12)
             }
           }
-          return { text: "", highlightUrl: "" }
+          return { text: "", highlightUrl: "", context: "" }
         }
       },
       async (results) => {
@@ -146,7 +158,8 @@ chrome.contextMenus.onClicked.addListener((item, tab) => {
         await createWord({
           word: ret.text,
           url: ret.highlightUrl,
-          definition: translation?.text
+          definition: translation?.text,
+          context: ret.context || undefined
         })
       }
     )
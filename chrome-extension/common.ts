@@ -1,6 +1,11 @@
 import { setLogout } from "~auth-slice"
 import { store } from "~store"
-import { setHasMore, setIsLoading, setWords } from "~words-slice"
+import {
+  setHasMore,
+  setIsLoading,
+  setNextCursor,
+  setWords
+} from "~words-slice"
 
 export {}
 
@@ -63,41 +68,44 @@ export const loadMoreWords = async () => {
   const isLoading = store.getState().words.isLoading
   if (isLoading) return
   const words = store.getState().words?.words ?? []
-  const offset = words?.length ?? 0
+  const cursor = store.getState().words.nextCursor
+  if (words.length > 0 && !cursor) return
   store.dispatch(setIsLoading(true))
   try {
-    const newWords = await fetchWords(offset)
-    store.dispatch(setWords([...words, ...newWords]))
-    store.dispatch(setHasMore(newWords.length >= PAGE_SIZE))
+    const page = await fetchWords(cursor)
+    store.dispatch(setWords([...words, ...page.items]))
+    store.dispatch(setNextCursor(page.next_cursor ?? null))
+    store.dispatch(setHasMore(!!page.next_cursor))
   } finally {
     store.dispatch(setIsLoading(false))
   }
 }
 
+const EMPTY_PAGE: WordPage = { items: [], next_cursor: null, total: 0 }
+
 export const fetchWords = async (
-  offset: number = 0,
+  cursor: string | null = null,
   size: number = PAGE_SIZE
 ) => {
   try {
-    const response = await fetch(
-      `${API_HOST}/api/v1/words?offset=${offset}&size=${size}`,
-      {
-        headers: {
-          Authorization: `Bearer ${store.getState().auth.access_token}`
-        }
+    const params = new URLSearchParams({ size: `${size}` })
+    if (cursor) params.set("cursor", cursor)
+    const response = await fetch(`${API_HOST}/api/v1/words?${params}`, {
+      headers: {
+        Authorization: `Bearer ${store.getState().auth.access_token}`
       }
-    )
+    })
     if (response.ok) {
-      return (await response.json()) as Words
+      return (await response.json()) as WordPage
     } else if (response.status === 401) {
       store.dispatch(setLogout())
-      return [] as Words
+      return EMPTY_PAGE
     } else {
       throw Error(`Error: ${response.statusText} (${response.status})`)
     }
   } catch (error) {
     console.error("Error fetching words:", error)
-    return [] as Words
+    return EMPTY_PAGE
   }
 }
 
@@ -174,13 +182,30 @@ export type Word = {
   word: string
   definition?: string
   url?: string
+  urls?: string[]
+  context?: string
+  part_of_speech?: string
+  senses?: Sense[]
+  deck_id?: number | null
+  tags?: string[]
   username?: string
   created_at?: number
   updated_at?: number
 }
 
+export type Sense = {
+  definition: string
+  examples?: string[]
+}
+
 export type Words = Word[]
 
+export type WordPage = {
+  items: Words
+  next_cursor?: string | null
+  total: number
+}
+
 export function isExpired(jwt: string) {
   try {
     const claims = jwt.split(".")[1]
@@ -15,7 +15,12 @@ import { setLogout, setTokens } from "~auth-slice"
 import HeaderView from "~HeaderView"
 import WelcomeView from "~WelcomeView"
 import WordListView from "~WordListView"
-import { resetWords, setWords } from "~words-slice"
+import {
+  resetWords,
+  setHasMore,
+  setNextCursor,
+  setWords
+} from "~words-slice"
 
 const queryClient = new QueryClient()
 
@@ -58,9 +63,12 @@ const Layout = () => {
 chrome.runtime.onMessage.addListener(async (message: Message) => {
   if (message.type === "word_created") {
     const words = store.getState().words.words ?? []
-    const latestWords = await fetchWords(message.word)
+    const page = await fetchWords()
+    const latestWords = page.items
     if (words.length == 0) {
       store.dispatch(setWords([...latestWords]))
+      store.dispatch(setNextCursor(page.next_cursor ?? null))
+      store.dispatch(setHasMore(!!page.next_cursor))
       return
     }
     const firstWord = words.at(0)
@@ -6,6 +6,7 @@ export interface WordsState {
   words: Words
   isLoading: boolean
   hasMore: boolean
+  nextCursor: string | null
 }
 
 const wordsSlice = createSlice({
@@ -13,7 +14,8 @@ const wordsSlice = createSlice({
   initialState: {
     words: [],
     isLoading: false,
-    hasMore: false
+    hasMore: false,
+    nextCursor: null
   } as WordsState,
   reducers: {
     setWords: (state, action) => {
@@ -28,15 +30,25 @@ const wordsSlice = createSlice({
     setHasMore: (state, action) => {
       state.hasMore = action.payload
     },
+    setNextCursor: (state, action) => {
+      state.nextCursor = action.payload
+    },
     resetWords: (state) => {
       state.words = []
       state.isLoading = false
       state.hasMore = false
+      state.nextCursor = null
     }
   }
 })
 
-export const { setWords, removeWord, setIsLoading, setHasMore, resetWords } =
-  wordsSlice.actions
+export const {
+  setWords,
+  removeWord,
+  setIsLoading,
+  setHasMore,
+  setNextCursor,
+  resetWords
+} = wordsSlice.actions
 
 export default wordsSlice.reducer
@@ -0,0 +1,164 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{error, Error, HttpMessage};
+
+use engine::tokens::{READ_SCOPE, SCOPES, WRITE_SCOPE};
+
+use super::oidc::Claims;
+
+/// The group of the users who may call the moderation and operations endpoints
+pub const ADMIN_GROUP: &str = "admin";
+
+impl Claims {
+    /// Whether the user may call the moderation and operations endpoints
+    pub fn is_admin(&self) -> bool {
+        self.groups.iter().any(|group| group == ADMIN_GROUP)
+    }
+
+    /// Whether the token allows what the scope stands for
+    ///
+    /// Tokens with none of the scopes of this API, as issued when signing in
+    /// to the app, may do anything the user can.
+    pub fn allows(&self, scope: &str) -> bool {
+        let mut api_scopes = self
+            .scopes
+            .iter()
+            .filter(|s| SCOPES.contains(&s.as_str()))
+            .peekable();
+        api_scopes.peek().is_none() || api_scopes.any(|s| s == scope)
+    }
+}
+
+/// Requires the `read` scope to read data and the `write` scope to change it
+///
+/// Wraps the routes behind the authentication middleware, as with
+/// `.wrap(from_fn(require_scope))`.
+pub async fn require_scope(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let scope = if req.method().is_safe() {
+        READ_SCOPE
+    } else {
+        WRITE_SCOPE
+    };
+    let allowed = req
+        .extensions()
+        .get::<Claims>()
+        .is_some_and(|claims| claims.allows(scope));
+    if !allowed {
+        let error = error::ErrorForbidden(format!("Token is missing the {scope} scope"));
+        return Ok(req.error_response(error).map_into_right_body());
+    }
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+/// Requires the user to be in the admin group
+///
+/// Wraps a route behind the authentication middleware, as with
+/// `#[get("/path", wrap = "from_fn(require_admin)")]`.
+pub async fn require_admin(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let is_admin = req
+        .extensions()
+        .get::<Claims>()
+        .is_some_and(Claims::is_admin);
+    if !is_admin {
+        let error = error::ErrorForbidden("Requires the admin role");
+        return Ok(req.error_response(error).map_into_right_body());
+    }
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+/// Requires the user to have signed in with the identity provider, rather
+/// than to use a personal access token
+///
+/// Wraps a route behind the authentication middleware, as with
+/// `#[get("/path", wrap = "from_fn(require_session)")]`.
+pub async fn require_session(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let is_session = req
+        .extensions()
+        .get::<Claims>()
+        .is_some_and(|claims| claims.token_id.is_none());
+    if !is_session {
+        let error = error::ErrorForbidden("Requires signing in, not a personal access token");
+        return Ok(req.error_response(error).map_into_right_body());
+    }
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    fn claims(scopes: &[&str], groups: &[&str]) -> Claims {
+        Claims {
+            exp: 0,
+            user_id: "test_user".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+            token_id: None,
+        }
+    }
+
+    #[test]
+    fn test_claims() {
+        assert!(claims(&[], &[]).allows(WRITE_SCOPE));
+        assert!(claims(&["openid", "profile"], &[]).allows(WRITE_SCOPE));
+        assert!(claims(&["openid", READ_SCOPE], &[]).allows(READ_SCOPE));
+        assert!(!claims(&["openid", READ_SCOPE], &[]).allows(WRITE_SCOPE));
+
+        assert!(claims(&[], &["readers", ADMIN_GROUP]).is_admin());
+        assert!(!claims(&[], &["readers"]).is_admin());
+    }
+
+    #[actix_web::test]
+    async fn test_guards() {
+        for (claims, get_status, post_status, admin_status) in [
+            (claims(&[], &[]), 200, 200, 403),
+            (claims(&[READ_SCOPE], &[]), 200, 403, 403),
+            (claims(&[WRITE_SCOPE], &[ADMIN_GROUP]), 403, 200, 200),
+        ] {
+            let app = init_service(
+                App::new()
+                    .wrap(from_fn(require_scope))
+                    .wrap_fn(move |req, srv| {
+                        req.extensions_mut().insert(claims.clone());
+                        actix_web::dev::Service::call(srv, req)
+                    })
+                    .route("/words", web::get().to(HttpResponse::Ok))
+                    .route("/words", web::post().to(HttpResponse::Ok))
+                    .service(
+                        web::resource("/admin")
+                            .wrap(from_fn(require_admin))
+                            .route(web::post().to(HttpResponse::Ok)),
+                    ),
+            )
+            .await;
+
+            for (req, status) in [
+                (TestRequest::get().uri("/words"), get_status),
+                (TestRequest::post().uri("/words"), post_status),
+                (TestRequest::post().uri("/admin"), admin_status),
+            ] {
+                let resp = call_service(&app, req.to_request()).await;
+                assert_eq!(resp.status().as_u16(), status);
+            }
+        }
+    }
+}
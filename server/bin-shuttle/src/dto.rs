@@ -1,6 +1,15 @@
-use engine::types::{MAX_DEFINITION_LENGTH, MAX_PAGE_SIZE, MAX_URL_LENGTH, MAX_WORD_LENGTH};
+use engine::export::ExportFormat;
+use engine::import::{ImportAction, ImportFormat, OnDuplicate};
+use engine::scheduler::SchedulerKind;
+use engine::translate::{Language, SourceLanguage};
+use engine::types::{
+    SortOrder, WordSort, MAX_CONTEXT_LENGTH, MAX_DEFINITION_LENGTH, MAX_DOMAIN_LENGTH,
+    MAX_NAME_LENGTH, MAX_PAGE_SIZE, MAX_PART_OF_SPEECH_LENGTH, MAX_SEARCH_QUERY_LENGTH, MAX_SENSES,
+    MAX_TAGS, MAX_URL_LENGTH, MAX_WORD_LENGTH,
+};
 use serde::{Deserialize, Serialize};
 use sqlx::types::chrono;
+use std::collections::HashMap;
 use utoipa::ToSchema;
 use validator::Validate;
 
@@ -11,20 +20,185 @@ use validator::Validate;
 /// {
 ///     "word": "hello",
 ///     "definition": "a greeting",
-///     "url": "https://example.com"
+///     "url": "https://example.com",
+///     "context": "She said hello and walked on.",
+///     "part_of_speech": "interjection",
+///     "senses": [
+///         {
+///             "definition": "a greeting",
+///             "examples": ["Hello, how are you?"]
+///         }
+///     ],
+///     "deck_id": 1,
+///     "tags": ["novel reading"]
 /// }
 /// ```
-#[derive(Serialize, Deserialize, ToSchema, Validate)]
+#[derive(Default, Serialize, Deserialize, ToSchema, Validate)]
 pub struct NewWord {
     #[validate(length(min = 1, max = MAX_WORD_LENGTH))]
     #[schema(example = "hello")]
     pub word: String,
+    /// Defaults to the definition of the first sense
     #[validate(length(min = 1, max = MAX_DEFINITION_LENGTH))]
     #[schema(example = "a greeting")]
     pub definition: Option<String>,
+    /// The page the word was saved from, omitted or empty for a word that was
+    /// not saved from a page, such as one typed in by hand
+    #[validate(length(min = 0, max = MAX_URL_LENGTH))]
+    #[schema(example = "https://example.com")]
+    pub url: Option<String>,
+    /// The sentence the word was found in
+    #[validate(length(min = 1, max = MAX_CONTEXT_LENGTH))]
+    #[schema(example = "She said hello and walked on.")]
+    pub context: Option<String>,
+    #[validate(length(min = 1, max = MAX_PART_OF_SPEECH_LENGTH))]
+    #[schema(example = "interjection")]
+    pub part_of_speech: Option<String>,
+    #[validate(length(max = MAX_SENSES), nested)]
+    pub senses: Option<Vec<Sense>>,
+    /// The deck the word belongs to
+    #[schema(example = 1)]
+    pub deck_id: Option<i32>,
+    /// Names of the tags of the word, tags the user does not have yet are created
+    #[validate(length(max = MAX_TAGS))]
+    #[schema(example = json!(["novel reading"]))]
+    pub tags: Option<Vec<String>>,
+}
+
+impl NewWord {
+    /// Create engine::types::NewWord for a user
+    pub fn into_engine(self, user_id: String) -> engine::types::NewWord {
+        let senses: Vec<engine::types::WordSense> = self
+            .senses
+            .unwrap_or_default()
+            .into_iter()
+            .map(|sense| sense.into())
+            .collect();
+        let definition = self
+            .definition
+            .or_else(|| senses.first().map(|sense| sense.definition.clone()))
+            .unwrap_or_default();
+        let mut new_word = engine::types::NewWord::new(
+            user_id,
+            self.word,
+            definition,
+            self.url.unwrap_or_default(),
+        )
+        .with_forgetting_rate(0.5)
+        .with_senses(senses)
+        .with_tags(self.tags.unwrap_or_default());
+        if let Some(context) = self.context {
+            new_word = new_word.with_context(context);
+        }
+        if let Some(part_of_speech) = self.part_of_speech {
+            new_word = new_word.with_part_of_speech(part_of_speech);
+        }
+        if let Some(deck_id) = self.deck_id {
+            new_word = new_word.with_deck(deck_id);
+        }
+        new_word
+    }
+}
+
+/// Update word, omitted fields are left unchanged
+///
+/// The context, part of speech and deck are cleared when set to null, and the
+/// URL when set to an empty string.
+///
+/// # Example
+/// ```json
+/// {
+///     "definition": "a friendly greeting",
+///     "context": null
+/// }
+/// ```
+#[derive(Default, Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateWord {
+    #[validate(length(min = 1, max = MAX_WORD_LENGTH))]
+    #[schema(example = "hello")]
+    pub word: Option<String>,
+    #[validate(length(min = 1, max = MAX_DEFINITION_LENGTH))]
+    #[schema(example = "a friendly greeting")]
+    pub definition: Option<String>,
     #[validate(length(min = 0, max = MAX_URL_LENGTH))]
     #[schema(example = "https://example.com")]
     pub url: Option<String>,
+    #[validate(length(min = 1, max = MAX_CONTEXT_LENGTH))]
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, nullable, example = "She said hello and walked on.")]
+    pub context: Option<Option<String>>,
+    #[validate(length(min = 1, max = MAX_PART_OF_SPEECH_LENGTH))]
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, nullable, example = "interjection")]
+    pub part_of_speech: Option<Option<String>>,
+    #[validate(length(max = MAX_SENSES), nested)]
+    pub senses: Option<Vec<Sense>>,
+    /// Moves the word to this deck, or out of its deck when null
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<i32>, nullable, example = 1)]
+    pub deck_id: Option<Option<i32>>,
+    /// Replaces the tags of the word
+    #[validate(length(max = MAX_TAGS))]
+    #[schema(example = json!(["work jargon"]))]
+    pub tags: Option<Vec<String>>,
+}
+
+/// Create engine::types::UpdateWord from update word
+impl From<UpdateWord> for engine::types::UpdateWord {
+    fn from(update_word: UpdateWord) -> Self {
+        Self {
+            word: update_word.word,
+            definition: update_word.definition,
+            url: update_word.url,
+            context: update_word.context,
+            part_of_speech: update_word.part_of_speech,
+            senses: update_word
+                .senses
+                .map(|senses| senses.into_iter().map(|sense| sense.into()).collect()),
+            deck_id: update_word.deck_id,
+            tags: update_word.tags,
+        }
+    }
+}
+
+/// Create engine::types::UpdateWord from new word, replacing every field, so
+/// omitted fields are cleared
+impl From<NewWord> for engine::types::UpdateWord {
+    fn from(new_word: NewWord) -> Self {
+        let new_word = new_word.into_engine(String::new());
+        Self {
+            word: Some(new_word.word),
+            definition: Some(new_word.definition),
+            url: Some(new_word.url),
+            context: Some(new_word.context),
+            part_of_speech: Some(new_word.part_of_speech),
+            senses: Some(new_word.senses),
+            deck_id: Some(new_word.deck_id),
+            tags: Some(new_word.tags),
+        }
+    }
+}
+
+/// Deserializes a field that is cleared when null, and left unchanged when
+/// omitted along with `#[serde(default)]`
+fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 /// Word
@@ -36,7 +210,10 @@ pub struct NewWord {
 ///     "word": "hello",
 ///     "definition": "a greeting",
 ///     "url": "https://example.com",
-///     "created_at": "2024-01-01T00:00:00Z"
+///     "urls": ["https://example.com"],
+///     "created_at": "2024-01-01T00:00:00Z",
+///     "retention": 0.9,
+///     "next_review_at": "2024-01-02T00:00:00Z"
 /// }
 /// ```
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -49,8 +226,30 @@ pub struct Word {
     pub definition: Option<String>,
     #[schema(example = "https://example.com")]
     pub url: Option<String>,
+    /// The sentence the word was found in
+    #[schema(example = "She said hello and walked on.")]
+    pub context: Option<String>,
+    #[schema(example = "interjection")]
+    pub part_of_speech: Option<String>,
+    #[serde(default)]
+    pub senses: Vec<Sense>,
+    /// Every page the word was saved from, oldest first
+    #[serde(default)]
+    #[schema(example = json!(["https://example.com"]))]
+    pub urls: Vec<String>,
+    #[schema(example = 1)]
+    pub deck_id: Option<i32>,
+    /// Names of the tags of the word, alphabetically
+    #[serde(default)]
+    #[schema(example = json!(["novel reading"]))]
+    pub tags: Vec<String>,
     #[schema(example = "2024-01-01T00:00:00Z", value_type = String)]
     pub created_at: chrono::NaiveDateTime,
+    /// Predicted probability of recalling the word now, between 0 and 1
+    #[schema(example = 0.9)]
+    pub retention: f64,
+    #[schema(example = "2024-01-02T00:00:00Z", value_type = Option<String>)]
+    pub next_review_at: Option<chrono::NaiveDateTime>,
 }
 
 /// Create word from engine::types::Word
@@ -62,7 +261,9 @@ pub struct Word {
 ///     "word": "hello",
 ///     "definition": "a greeting",
 ///     "url": "https://example.com",
-///     "created_at": "2024-01-01T00:00:00Z"
+///     "created_at": "2024-01-01T00:00:00Z",
+///     "retention": 0.9,
+///     "next_review_at": "2024-01-02T00:00:00Z"
 /// }
 impl From<engine::types::Word> for Word {
     fn from(word: engine::types::Word) -> Self {
@@ -71,7 +272,50 @@ impl From<engine::types::Word> for Word {
             word: word.word,
             definition: Some(word.definition),
             url: Some(word.url),
+            context: word.context,
+            part_of_speech: word.part_of_speech,
+            senses: word.senses.into_iter().map(|sense| sense.into()).collect(),
+            urls: word.urls,
+            deck_id: word.deck_id,
+            tags: word.tags,
             created_at: word.date_added,
+            retention: word.retention,
+            next_review_at: word.next_review_date,
+        }
+    }
+}
+
+/// Added word
+///
+/// # Example
+/// ```json
+/// {
+///     "id": 1,
+///     "word": "hello",
+///     "definition": "a greeting",
+///     "url": "https://example.com",
+///     "urls": ["https://example.com", "https://example.org"],
+///     "created_at": "2024-01-01T00:00:00Z",
+///     "retention": 0.9,
+///     "next_review_at": "2024-01-02T00:00:00Z",
+///     "merged": true
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AddedWord {
+    #[serde(flatten)]
+    pub word: Word,
+    /// The user already had the word, and the new one was merged into it
+    #[schema(example = true)]
+    pub merged: bool,
+}
+
+/// Create added word from engine::types::InsertedWord
+impl From<engine::types::InsertedWord> for AddedWord {
+    fn from(inserted: engine::types::InsertedWord) -> Self {
+        Self {
+            word: inserted.word.into(),
+            merged: inserted.merged,
         }
     }
 }
@@ -81,13 +325,62 @@ impl From<engine::types::Word> for Word {
 /// # Example
 /// ```json
 /// {
-///     "text": "Hello, world!"
+///     "text": "你好，世界！",
+///     "source": "en",
+///     "target": "zh"
 /// }
 /// ```
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct TranslateResponse {
-    #[schema(example = "Hello, world!")]
+    #[schema(example = "你好，世界！")]
     pub text: String,
+    /// The language translated from, detected when the request asked for `auto`
+    #[schema(example = "en", value_type = Option<String>)]
+    pub source: Option<Language>,
+    /// The language translated into
+    #[schema(example = "zh", value_type = String)]
+    pub target: Language,
+}
+
+/// Translation cache statistics since the server started
+///
+/// # Example
+/// ```json
+/// {
+///     "memory_hits": 120,
+///     "database_hits": 30,
+///     "misses": 50,
+///     "hit_rate": 0.75
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TranslationCacheStats {
+    /// Translations served from the memory of this server
+    #[schema(example = 120)]
+    pub memory_hits: u64,
+    /// Translations served from the database
+    #[schema(example = 30)]
+    pub database_hits: u64,
+    /// Translations requested from the provider
+    #[schema(example = 50)]
+    pub misses: u64,
+    /// Fraction of translations served from the cache, absent before the first translation
+    #[schema(example = 0.75)]
+    pub hit_rate: Option<f64>,
+}
+
+/// Create translation cache statistics from engine::translation_cache::CacheStats
+impl From<engine::translation_cache::CacheStats> for TranslationCacheStats {
+    fn from(stats: engine::translation_cache::CacheStats) -> Self {
+        let hits = stats.memory_hits + stats.database_hits;
+        let total = hits + stats.misses;
+        Self {
+            memory_hits: stats.memory_hits,
+            database_hits: stats.database_hits,
+            misses: stats.misses,
+            hit_rate: (total > 0).then(|| hits as f64 / total as f64),
+        }
+    }
 }
 
 /// Translate params
@@ -95,7 +388,9 @@ pub struct TranslateResponse {
 /// # Example
 /// ```json
 /// {
-///     "text": "Hello, world!"
+///     "text": "Hello, world!",
+///     "source": "auto",
+///     "target": "zh"
 /// }
 /// ```
 #[derive(Deserialize, Validate)]
@@ -103,6 +398,102 @@ pub struct TranslateParams {
     /// The text to translate
     #[validate(length(min = 0, max = MAX_WORD_LENGTH))]
     pub text: String,
+    /// BCP-47 tag of the language to translate from, or `auto` to detect it
+    #[serde(default)]
+    pub source: SourceLanguage,
+    /// BCP-47 tag of the language to translate into, defaults to the user's target language
+    pub target: Option<Language>,
+}
+
+/// Lookup params
+#[derive(Deserialize, Validate)]
+pub struct LookupParams {
+    /// The word to look up
+    #[validate(length(min = 1, max = MAX_WORD_LENGTH))]
+    pub word: String,
+    /// BCP-47 tag of the language of the word, defaults to English
+    pub language: Option<Language>,
+}
+
+/// Dictionary entry of a word as one part of speech
+///
+/// # Example
+/// ```json
+/// {
+///     "word": "serendipity",
+///     "part_of_speech": "noun",
+///     "pronunciations": ["/ˌsɛɹ.ənˈdɪp.ɪ.ti/"],
+///     "senses": [
+///         {
+///             "definition": "A combination of events which have come together by chance to make a surprisingly good outcome.",
+///             "examples": ["It was pure serendipity that we met."]
+///         }
+///     ]
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DictionaryEntry {
+    #[schema(example = "serendipity")]
+    pub word: String,
+    #[schema(example = "noun")]
+    pub part_of_speech: String,
+    /// Pronunciations in IPA
+    #[schema(example = json!(["/ˌsɛɹ.ənˈdɪp.ɪ.ti/"]))]
+    pub pronunciations: Vec<String>,
+    pub senses: Vec<Sense>,
+}
+
+/// One meaning of a word
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct Sense {
+    #[validate(length(min = 1, max = MAX_DEFINITION_LENGTH))]
+    #[schema(
+        example = "A combination of events which have come together by chance to make a surprisingly good outcome."
+    )]
+    pub definition: String,
+    #[validate(length(max = MAX_SENSES))]
+    #[serde(default)]
+    #[schema(example = json!(["It was pure serendipity that we met."]))]
+    pub examples: Vec<String>,
+}
+
+/// Create sense from engine::types::WordSense
+impl From<engine::types::WordSense> for Sense {
+    fn from(sense: engine::types::WordSense) -> Self {
+        Self {
+            definition: sense.definition,
+            examples: sense.examples,
+        }
+    }
+}
+
+/// Create engine::types::WordSense from sense
+impl From<Sense> for engine::types::WordSense {
+    fn from(sense: Sense) -> Self {
+        Self {
+            definition: sense.definition,
+            examples: sense.examples,
+        }
+    }
+}
+
+/// Create dictionary entry from engine::dictionary::DictionaryEntry
+impl From<engine::dictionary::DictionaryEntry> for DictionaryEntry {
+    fn from(entry: engine::dictionary::DictionaryEntry) -> Self {
+        Self {
+            word: entry.word,
+            part_of_speech: entry.part_of_speech,
+            pronunciations: entry.pronunciations,
+            senses: entry
+                .senses
+                .into_iter()
+                .map(|sense| Sense {
+                    definition: sense.definition,
+                    examples: sense.examples,
+                })
+                .collect(),
+        }
+    }
 }
 
 /// Review params
@@ -124,6 +515,216 @@ pub struct ReviewParams {
     pub recall_score: i32,
 }
 
+/// Review session
+///
+/// # Example
+/// ```json
+/// {
+///     "id": 1,
+///     "word_id": 1,
+///     "recall_score": 3,
+///     "reviewed_at": "2024-01-01T00:00:00Z",
+///     "time_to_forget_seconds": 86400,
+///     "next_review_at": "2024-01-02T00:00:00Z"
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ReviewSession {
+    #[schema(example = 1)]
+    pub id: i32,
+    #[schema(example = 1)]
+    pub word_id: i32,
+    #[schema(example = 3)]
+    pub recall_score: Option<i32>,
+    #[schema(example = "2024-01-01T00:00:00Z", value_type = String)]
+    pub reviewed_at: chrono::NaiveDateTime,
+    #[schema(example = 86400)]
+    pub time_to_forget_seconds: Option<i64>,
+    #[schema(example = "2024-01-02T00:00:00Z", value_type = Option<String>)]
+    pub next_review_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Create review session from engine::types::ReviewSession
+impl From<engine::types::ReviewSession> for ReviewSession {
+    fn from(session: engine::types::ReviewSession) -> Self {
+        Self {
+            id: session.session_id,
+            word_id: session.word_id,
+            recall_score: session.recall_score,
+            reviewed_at: session.review_date,
+            time_to_forget_seconds: session.time_to_forget.map(|d| d.num_seconds()),
+            next_review_at: session.next_review_date,
+        }
+    }
+}
+
+/// Review queue
+///
+/// # Example
+/// ```json
+/// {
+///     "words": [],
+///     "due_count": 15,
+///     "new_count": 3,
+///     "reviews_remaining_today": 10,
+///     "new_remaining_today": 2,
+///     "due_today": 12
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ReviewQueue {
+    /// Words to review now, due words first, then new words
+    pub words: Vec<Word>,
+    /// Reviewed words that are due, regardless of the daily limit
+    #[schema(example = 15)]
+    pub due_count: i64,
+    /// Words that have never been reviewed
+    #[schema(example = 3)]
+    pub new_count: i64,
+    /// Reviews left under today's limit
+    #[schema(example = 10)]
+    pub reviews_remaining_today: i64,
+    /// New words left under today's limit
+    #[schema(example = 2)]
+    pub new_remaining_today: i64,
+    /// Words the user can still study today within the daily limits
+    #[schema(example = 12)]
+    pub due_today: i64,
+}
+
+/// Create review queue from engine::types::ReviewQueue
+impl From<engine::types::ReviewQueue> for ReviewQueue {
+    fn from(queue: engine::types::ReviewQueue) -> Self {
+        Self {
+            due_today: queue.due_count.min(queue.reviews_remaining)
+                + queue.new_count.min(queue.new_remaining),
+            words: queue.words.into_iter().map(|w| w.into()).collect(),
+            due_count: queue.due_count,
+            new_count: queue.new_count,
+            reviews_remaining_today: queue.reviews_remaining,
+            new_remaining_today: queue.new_remaining,
+        }
+    }
+}
+
+/// One page of words
+///
+/// # Example
+/// ```json
+/// {
+///     "items": [],
+///     "next_cursor": "eyJPZmZzZXQiOjEwfQ",
+///     "total": 42
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WordPage {
+    /// Words on this page
+    pub items: Vec<Word>,
+    /// Pass as `cursor` to retrieve the next page, absent on the last page
+    #[schema(example = "eyJPZmZzZXQiOjEwfQ")]
+    pub next_cursor: Option<String>,
+    /// Words matching the query across all pages
+    #[schema(example = 42)]
+    pub total: i64,
+}
+
+/// Create word page from engine::types::WordPage
+impl From<engine::types::WordPage> for WordPage {
+    fn from(page: engine::types::WordPage) -> Self {
+        Self {
+            items: page.words.into_iter().map(|w| w.into()).collect(),
+            next_cursor: page.next_cursor,
+            total: page.total,
+        }
+    }
+}
+
+/// Review queue params
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct QueueParams {
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE))]
+    pub size: Option<u64>,
+    /// Only words with the tag of this name
+    #[validate(length(min = 1, max = MAX_NAME_LENGTH))]
+    pub tag: Option<String>,
+    /// Only words in this deck
+    pub deck_id: Option<i32>,
+}
+
+/// Settings
+///
+/// # Example
+/// ```json
+/// {
+///     "scheduler": "sm2",
+///     "new_words_per_day": 20,
+///     "max_reviews_per_day": 200,
+///     "target_language": "zh",
+///     "lemmatize": false
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Settings {
+    /// The spaced-repetition algorithm, either `sm2` or `fsrs`
+    #[schema(example = "sm2", value_type = String)]
+    pub scheduler: SchedulerKind,
+    /// New words introduced per day
+    #[schema(example = 20)]
+    pub new_words_per_day: i32,
+    /// Reviews of known words per day
+    #[schema(example = 200)]
+    pub max_reviews_per_day: i32,
+    /// BCP-47 tag of the language words are translated into by default
+    #[schema(example = "zh", value_type = String)]
+    pub target_language: Language,
+    /// Whether English inflections, such as "ran" for "run", count as the same
+    /// word, off by default. Only base forms in the English dictionary are used.
+    #[schema(example = false)]
+    pub lemmatize: bool,
+}
+
+/// Create settings from engine::types::UserSettings
+impl From<engine::types::UserSettings> for Settings {
+    fn from(settings: engine::types::UserSettings) -> Self {
+        Self {
+            scheduler: settings.scheduler,
+            new_words_per_day: settings.new_words_per_day,
+            max_reviews_per_day: settings.max_reviews_per_day,
+            target_language: settings.target_language,
+            lemmatize: settings.lemmatize,
+        }
+    }
+}
+
+/// Update settings, omitted fields are left unchanged
+///
+/// # Example
+/// ```json
+/// {
+///     "scheduler": "fsrs",
+///     "new_words_per_day": 10
+/// }
+/// ```
+#[derive(Default, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSettings {
+    /// The spaced-repetition algorithm, either `sm2` or `fsrs`
+    #[schema(example = "fsrs", value_type = Option<String>)]
+    pub scheduler: Option<SchedulerKind>,
+    /// New words introduced per day
+    #[schema(example = 10)]
+    pub new_words_per_day: Option<i32>,
+    /// Reviews of known words per day
+    #[schema(example = 200)]
+    pub max_reviews_per_day: Option<i32>,
+    /// BCP-47 tag of the language words are translated into by default
+    #[schema(example = "pt-BR", value_type = Option<String>)]
+    pub target_language: Option<Language>,
+    /// Whether English inflections, such as "ran" for "run", count as the same word
+    #[schema(example = false)]
+    pub lemmatize: Option<bool>,
+}
+
 /// Pagination params
 #[derive(Debug, Clone, Validate, Deserialize)]
 pub struct PaginationParams {
@@ -131,4 +732,582 @@ pub struct PaginationParams {
     pub page: Option<u64>,
     #[validate(range(min = 1, max = MAX_PAGE_SIZE))]
     pub size: Option<u64>,
+    /// The `next_cursor` of the previous page, takes precedence over `page`
+    pub cursor: Option<String>,
+}
+
+/// Search params
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct SearchParams {
+    /// Text to search for in words and definitions
+    #[validate(length(min = 1, max = MAX_SEARCH_QUERY_LENGTH))]
+    pub q: Option<String>,
+}
+
+/// Word filter params
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct WordFilterParams {
+    /// One of `alphabetical`, `date_added`, `next_review_date` or `retention`
+    pub sort: Option<WordSort>,
+    /// Either `asc` or `desc`
+    pub order: Option<SortOrder>,
+    /// Only words added on or after this date
+    pub added_from: Option<chrono::NaiveDate>,
+    /// Only words added on or before this date
+    pub added_to: Option<chrono::NaiveDate>,
+    /// Only words saved from this domain or its subdomains
+    #[validate(length(min = 1, max = MAX_DOMAIN_LENGTH))]
+    pub domain: Option<String>,
+    /// Only words that are due, or not due, for review
+    pub due: Option<bool>,
+    /// Only words with the tag of this name
+    #[validate(length(min = 1, max = MAX_NAME_LENGTH))]
+    pub tag: Option<String>,
+    /// Only words in this deck
+    pub deck_id: Option<i32>,
+}
+
+/// Create engine::types::WordFilter from word filter params
+impl From<WordFilterParams> for engine::types::WordFilter {
+    fn from(params: WordFilterParams) -> Self {
+        Self {
+            sort: params.sort.unwrap_or_default(),
+            order: params.order,
+            added_from: params.added_from,
+            added_to: params.added_to,
+            domain: params.domain,
+            due: params.due,
+            tag: params.tag,
+            deck_id: params.deck_id,
+        }
+    }
+}
+
+/// Tag
+///
+/// # Example
+/// ```json
+/// {
+///     "id": 1,
+///     "name": "novel reading",
+///     "word_count": 42,
+///     "created_at": "2024-01-01T00:00:00Z"
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Tag {
+    #[schema(example = 1)]
+    pub id: i32,
+    #[schema(example = "novel reading")]
+    pub name: String,
+    /// Number of words with the tag
+    #[schema(example = 42)]
+    pub word_count: i64,
+    #[schema(example = "2024-01-01T00:00:00Z", value_type = String)]
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Create tag from engine::types::Tag
+impl From<engine::types::Tag> for Tag {
+    fn from(tag: engine::types::Tag) -> Self {
+        Self {
+            id: tag.tag_id,
+            name: tag.name,
+            word_count: tag.word_count,
+            created_at: tag.created_at,
+        }
+    }
+}
+
+/// Deck
+///
+/// # Example
+/// ```json
+/// {
+///     "id": 1,
+///     "name": "Work jargon",
+///     "word_count": 42,
+///     "created_at": "2024-01-01T00:00:00Z"
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Deck {
+    #[schema(example = 1)]
+    pub id: i32,
+    #[schema(example = "Work jargon")]
+    pub name: String,
+    /// Number of words in the deck
+    #[schema(example = 42)]
+    pub word_count: i64,
+    #[schema(example = "2024-01-01T00:00:00Z", value_type = String)]
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Create deck from engine::types::Deck
+impl From<engine::types::Deck> for Deck {
+    fn from(deck: engine::types::Deck) -> Self {
+        Self {
+            id: deck.deck_id,
+            name: deck.name,
+            word_count: deck.word_count,
+            created_at: deck.created_at,
+        }
+    }
+}
+
+/// The name of a new or renamed tag or deck
+///
+/// # Example
+/// ```json
+/// {
+///     "name": "novel reading"
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct NameParams {
+    /// Unique per user regardless of case
+    #[validate(length(min = 1, max = MAX_NAME_LENGTH))]
+    #[schema(example = "novel reading")]
+    pub name: String,
+}
+
+/// A new personal access token
+///
+/// # Example
+/// ```json
+/// {
+///     "name": "Nightly backup",
+///     "scopes": ["read"],
+///     "expires_in_days": 30
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct NewAccessToken {
+    #[schema(example = "Nightly backup")]
+    pub name: String,
+    /// Some of `read` and `write`
+    #[schema(example = json!(["read"]))]
+    pub scopes: Vec<String>,
+    /// Between 1 and 365, defaults to 90
+    #[schema(example = 30)]
+    pub expires_in_days: Option<i32>,
+}
+
+/// Personal access token, without the token itself
+///
+/// # Example
+/// ```json
+/// {
+///     "id": 1,
+///     "name": "Nightly backup",
+///     "prefix": "afw_Xk3v9QzL",
+///     "scopes": ["read"],
+///     "created_at": "2024-01-01T00:00:00Z",
+///     "expires_at": "2024-01-31T00:00:00Z",
+///     "last_used_at": "2024-01-02T03:00:00Z"
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AccessToken {
+    #[schema(example = 1)]
+    pub id: i32,
+    #[schema(example = "Nightly backup")]
+    pub name: String,
+    /// The start of the token, to recognize it by
+    #[schema(example = "afw_Xk3v9QzL")]
+    pub prefix: String,
+    #[schema(example = json!(["read"]))]
+    pub scopes: Vec<String>,
+    #[schema(example = "2024-01-01T00:00:00Z", value_type = String)]
+    pub created_at: chrono::NaiveDateTime,
+    #[schema(example = "2024-01-31T00:00:00Z", value_type = String)]
+    pub expires_at: chrono::NaiveDateTime,
+    #[schema(example = "2024-01-02T03:00:00Z", value_type = Option<String>)]
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Create access token from engine::tokens::AccessToken
+impl From<engine::tokens::AccessToken> for AccessToken {
+    fn from(access_token: engine::tokens::AccessToken) -> Self {
+        Self {
+            id: access_token.token_id,
+            name: access_token.name,
+            prefix: access_token.prefix,
+            scopes: access_token.scopes,
+            created_at: access_token.created_at,
+            expires_at: access_token.expires_at,
+            last_used_at: access_token.last_used_at,
+        }
+    }
+}
+
+/// Personal access token just created, with the token itself, which is shown only once
+///
+/// # Example
+/// ```json
+/// {
+///     "token": "afw_Xk3v9QzLr8B2mN5pW7tY1cF4hJ6kD0sA9eG3iU2oQ5w",
+///     "access_token": {
+///         "id": 1,
+///         "name": "Nightly backup",
+///         "prefix": "afw_Xk3v9QzL",
+///         "scopes": ["read"],
+///         "created_at": "2024-01-01T00:00:00Z",
+///         "expires_at": "2024-01-31T00:00:00Z",
+///         "last_used_at": null
+///     }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CreatedAccessToken {
+    /// Sent as a bearer token
+    #[schema(example = "afw_Xk3v9QzLr8B2mN5pW7tY1cF4hJ6kD0sA9eG3iU2oQ5w")]
+    pub token: String,
+    pub access_token: AccessToken,
+}
+
+/// Create created access token from engine::tokens::CreatedAccessToken
+impl From<engine::tokens::CreatedAccessToken> for CreatedAccessToken {
+    fn from(created: engine::tokens::CreatedAccessToken) -> Self {
+        Self {
+            token: created.token,
+            access_token: created.access_token.into(),
+        }
+    }
+}
+
+/// Import params
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportParams {
+    /// One of `csv`, `tsv`, `anki` or `kindle`, detected from the file if not given
+    pub format: Option<ImportFormat>,
+    /// Report what would be imported without changing anything
+    pub dry_run: Option<bool>,
+    /// Either `skip` or `merge`
+    pub on_duplicate: Option<OnDuplicate>,
+    /// The deck every imported word is added to
+    pub deck_id: Option<i32>,
+}
+
+/// Outcome of importing a row
+///
+/// # Example
+/// ```json
+/// {
+///     "row": 2,
+///     "word": "hello",
+///     "action": "invalid",
+///     "errors": {
+///         "definition": ["No definition given or found in the dictionary"]
+///     }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportedRow {
+    /// Position of the row in the file, starting from 1
+    #[schema(example = 2)]
+    pub row: usize,
+    #[schema(example = "hello")]
+    pub word: String,
+    /// One of `insert`, `merge`, `skip` or `invalid`
+    #[schema(example = "invalid", value_type = String)]
+    pub action: ImportAction,
+    /// Why the row is invalid, by field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<HashMap<String, Vec<String>>>,
+}
+
+/// Create imported row from engine::import::ImportedRow
+impl From<engine::import::ImportedRow> for ImportedRow {
+    fn from(row: engine::import::ImportedRow) -> Self {
+        Self {
+            row: row.row,
+            word: row.word,
+            action: row.action,
+            errors: row.errors.map(|errors| {
+                errors
+                    .field_errors()
+                    .into_iter()
+                    .map(|(field, errors)| {
+                        let messages = errors
+                            .iter()
+                            .map(|e| e.message.as_ref().unwrap_or(&e.code).to_string())
+                            .collect();
+                        (field.to_string(), messages)
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// Import report
+///
+/// # Example
+/// ```json
+/// {
+///     "dry_run": false,
+///     "inserted": 40,
+///     "merged": 0,
+///     "skipped": 1,
+///     "invalid": 1,
+///     "rows": [
+///         {
+///             "row": 2,
+///             "word": "hello",
+///             "action": "insert"
+///         }
+///     ]
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportReport {
+    #[schema(example = false)]
+    pub dry_run: bool,
+    /// Number of new words
+    #[schema(example = 40)]
+    pub inserted: usize,
+    /// Number of duplicates merged into existing words
+    #[schema(example = 0)]
+    pub merged: usize,
+    /// Number of duplicates skipped
+    #[schema(example = 1)]
+    pub skipped: usize,
+    /// Number of invalid rows, which are never imported
+    #[schema(example = 1)]
+    pub invalid: usize,
+    pub rows: Vec<ImportedRow>,
+}
+
+/// Create import report from engine::import::ImportReport
+impl From<engine::import::ImportReport> for ImportReport {
+    fn from(report: engine::import::ImportReport) -> Self {
+        Self {
+            dry_run: report.dry_run,
+            inserted: report.inserted,
+            merged: report.merged,
+            skipped: report.skipped,
+            invalid: report.invalid,
+            rows: report.rows.into_iter().map(|r| r.into()).collect(),
+        }
+    }
+}
+
+/// Export params
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportParams {
+    /// One of `csv`, `json` or `anki`, defaults to `json`
+    pub format: Option<ExportFormat>,
+}
+
+/// Receipt of the deletion of all data of a user
+///
+/// # Example
+/// ```json
+/// {
+///     "user_id": "test_user",
+///     "words": 42,
+///     "review_sessions": 120,
+///     "forgetting_curves": 42,
+///     "tags": 3,
+///     "decks": 1,
+///     "settings": true,
+///     "access_tokens": 2,
+///     "deleted_at": "2024-01-01T00:00:00Z"
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DeletionReceipt {
+    #[schema(example = "test_user")]
+    pub user_id: String,
+    /// Number of words deleted
+    #[schema(example = 42)]
+    pub words: u64,
+    /// Number of review sessions deleted
+    #[schema(example = 120)]
+    pub review_sessions: u64,
+    /// Number of forgetting curves deleted
+    #[schema(example = 42)]
+    pub forgetting_curves: u64,
+    /// Number of tags deleted
+    #[schema(example = 3)]
+    pub tags: u64,
+    /// Number of decks deleted
+    #[schema(example = 1)]
+    pub decks: u64,
+    /// Whether saved settings were deleted
+    #[schema(example = true)]
+    pub settings: bool,
+    /// Number of personal access tokens deleted
+    #[schema(example = 2)]
+    pub access_tokens: u64,
+    #[schema(example = "2024-01-01T00:00:00Z", value_type = String)]
+    pub deleted_at: chrono::NaiveDateTime,
+}
+
+/// Create deletion receipt from engine::types::DeletedUserData
+impl From<engine::types::DeletedUserData> for DeletionReceipt {
+    fn from(deleted: engine::types::DeletedUserData) -> Self {
+        Self {
+            user_id: deleted.user_id,
+            words: deleted.words,
+            review_sessions: deleted.review_sessions,
+            forgetting_curves: deleted.forgetting_curves,
+            tags: deleted.tags,
+            decks: deleted.decks,
+            settings: deleted.settings,
+            access_tokens: deleted.access_tokens,
+            deleted_at: deleted.deleted_at,
+        }
+    }
+}
+
+/// Stats params
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatsParams {
+    /// The number of days of history, max 365
+    pub days: Option<i32>,
+    /// The number of days of due reviews forecast, max 365
+    pub forecast_days: Option<i32>,
+}
+
+/// A count on a day
+///
+/// # Example
+/// ```json
+/// {
+///     "day": "2024-01-01",
+///     "count": 12
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DailyCount {
+    #[schema(example = "2024-01-01", value_type = String)]
+    pub day: chrono::NaiveDate,
+    #[schema(example = 12)]
+    pub count: i64,
+}
+
+/// Create daily count from engine::stats::DailyCount
+impl From<engine::stats::DailyCount> for DailyCount {
+    fn from(count: engine::stats::DailyCount) -> Self {
+        Self {
+            day: count.day,
+            count: count.count,
+        }
+    }
+}
+
+/// How well words were recalled after being given a recall score
+///
+/// # Example
+/// ```json
+/// {
+///     "recall_score": 3,
+///     "reviews": 40,
+///     "followed_up": 30,
+///     "retained": 24,
+///     "retention_rate": 0.8
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RecallScoreRetention {
+    #[schema(example = 3)]
+    pub recall_score: i32,
+    /// Reviews with this score
+    #[schema(example = 40)]
+    pub reviews: i64,
+    /// Reviews with this score the word was reviewed again after
+    #[schema(example = 30)]
+    pub followed_up: i64,
+    /// Follow-up reviews with a recall score of 3 or more
+    #[schema(example = 24)]
+    pub retained: i64,
+    /// `retained / followed_up`, missing without follow-ups
+    #[schema(example = 0.8)]
+    pub retention_rate: Option<f64>,
+}
+
+/// Create recall score retention from engine::stats::RecallScoreRetention
+impl From<engine::stats::RecallScoreRetention> for RecallScoreRetention {
+    fn from(retention: engine::stats::RecallScoreRetention) -> Self {
+        Self {
+            recall_score: retention.recall_score,
+            reviews: retention.reviews,
+            followed_up: retention.followed_up,
+            retained: retention.retained,
+            retention_rate: retention.retention_rate,
+        }
+    }
+}
+
+/// Learning statistics
+///
+/// # Example
+/// ```json
+/// {
+///     "words_added": [{ "day": "2024-01-01", "count": 3 }],
+///     "reviews": [{ "day": "2024-01-01", "count": 12 }],
+///     "retention_by_recall_score": [
+///         {
+///             "recall_score": 3,
+///             "reviews": 40,
+///             "followed_up": 30,
+///             "retained": 24,
+///             "retention_rate": 0.8
+///         }
+///     ],
+///     "current_streak": 5,
+///     "longest_streak": 14,
+///     "new_words": 20,
+///     "learning_words": 35,
+///     "mature_words": 120,
+///     "forecast": [{ "day": "2024-01-02", "count": 15 }]
+/// }
+/// ```
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Stats {
+    /// Words added per day, oldest first and ending today
+    pub words_added: Vec<DailyCount>,
+    /// Reviews per day, oldest first and ending today
+    pub reviews: Vec<DailyCount>,
+    /// For each recall score from 1 to 5
+    pub retention_by_recall_score: Vec<RecallScoreRetention>,
+    /// Consecutive days with reviews up to today, or yesterday if there are no reviews today yet
+    #[schema(example = 5)]
+    pub current_streak: i64,
+    #[schema(example = 14)]
+    pub longest_streak: i64,
+    /// Words never reviewed
+    #[schema(example = 20)]
+    pub new_words: i64,
+    /// Words reviewed, with an interval shorter than 21 days
+    #[schema(example = 35)]
+    pub learning_words: i64,
+    /// Words with an interval of 21 days or more
+    #[schema(example = 120)]
+    pub mature_words: i64,
+    /// Reviews due per day, starting today with the overdue ones
+    pub forecast: Vec<DailyCount>,
+}
+
+/// Create stats from engine::stats::Stats
+impl From<engine::stats::Stats> for Stats {
+    fn from(stats: engine::stats::Stats) -> Self {
+        Self {
+            words_added: stats.words_added.into_iter().map(|c| c.into()).collect(),
+            reviews: stats.reviews.into_iter().map(|c| c.into()).collect(),
+            retention_by_recall_score: stats
+                .retention_by_recall_score
+                .into_iter()
+                .map(|r| r.into())
+                .collect(),
+            current_streak: stats.streaks.current,
+            longest_streak: stats.streaks.longest,
+            new_words: stats.maturity.new,
+            learning_words: stats.maturity.learning,
+            mature_words: stats.maturity.mature,
+            forecast: stats.forecast.into_iter().map(|c| c.into()).collect(),
+        }
+    }
 }
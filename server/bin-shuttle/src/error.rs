@@ -1,5 +1,5 @@
 use actix_web::{error, error::Error as ActixError};
-use engine::error::Error as EngineError;
+use engine::error::{Error as EngineError, TranslationError};
 
 pub(crate) trait IntoActixError {
     fn into_actix_error(self) -> ActixError;
@@ -11,7 +11,29 @@ impl IntoActixError for EngineError {
             EngineError::Validation(e) => error::ErrorBadRequest(e),
             EngineError::RowNotFound => error::ErrorNotFound("Record not found"),
             EngineError::Conflict(e) => error::ErrorConflict(e),
+            EngineError::Translation(e) => e.into_actix_error(),
+            EngineError::ThirdParty(_) => error::ErrorBadGateway("Third-party service failed"),
             _ => error::ErrorInternalServerError("Internal server error"),
         }
     }
 }
+
+impl IntoActixError for TranslationError {
+    fn into_actix_error(self) -> ActixError {
+        match self {
+            TranslationError::QuotaExceeded | TranslationError::RateLimited => {
+                error::ErrorTooManyRequests(self)
+            }
+            TranslationError::UnsupportedLanguage(_) => error::ErrorBadRequest(self),
+            TranslationError::Timeout => error::ErrorGatewayTimeout(self),
+            TranslationError::Unavailable(_) => {
+                error::ErrorServiceUnavailable("Translation provider unavailable")
+            }
+            // The deployment is misconfigured or the provider misbehaves, which
+            // the client cannot do anything about
+            TranslationError::InvalidKey | TranslationError::InvalidResponse(_) => {
+                error::ErrorBadGateway("Translation provider failed")
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use actix_web::{
     dev::ServiceRequest,
-    middleware::Logger,
+    middleware::{from_fn, Logger},
     web::{self, Data, ServiceConfig},
     HttpMessage,
 };
@@ -11,18 +11,32 @@ use actix_web_httpauth::{
     headers::www_authenticate::bearer::Bearer,
     middleware::HttpAuthentication,
 };
+use engine::dictionary::{Dictionary, PostgresDictionary};
 use engine::setup_database;
-use restful::{add, delete, list, retrieve, translate, AppState};
+use engine::translate::{
+    DeepLTranslator, FakeTranslator, GoogleTranslator, LibreTranslator, RetryingTranslator,
+    Translator,
+};
+use engine::translation_cache::CachedTranslator;
+use error::IntoActixError;
+use oidc::Claims;
+use restful::{
+    access_tokens, add, add_deck, add_tag, create_access_token, decks, delete, delete_account,
+    delete_deck, delete_tag, delete_user, export, history, import, list, lookup, queue,
+    rename_deck, rename_tag, replace, retrieve, review, revoke_access_token, settings, stats, tags,
+    translate, translation_cache, update, update_settings, AppState,
+};
 use shuttle_actix_web::ShuttleActixWeb;
 use shuttle_runtime::SecretStore;
 use sqlx::PgPool;
-use tokio::sync::Mutex;
+use tokio::time::Duration;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-mod cognito;
+mod authorization;
 mod dto;
 mod error;
+mod oidc;
 mod restful;
 
 #[derive(OpenApi)]
@@ -35,11 +49,63 @@ mod restful;
     paths(
         restful::retrieve,
         restful::add,
+        restful::update,
+        restful::replace,
         restful::list,
         restful::delete,
-        restful::translate
+        restful::translate,
+        restful::translation_cache,
+        restful::lookup,
+        restful::review,
+        restful::history,
+        restful::queue,
+        restful::settings,
+        restful::update_settings,
+        restful::tags,
+        restful::add_tag,
+        restful::rename_tag,
+        restful::delete_tag,
+        restful::decks,
+        restful::add_deck,
+        restful::rename_deck,
+        restful::delete_deck,
+        restful::import,
+        restful::export,
+        restful::delete_account,
+        restful::delete_user,
+        restful::access_tokens,
+        restful::create_access_token,
+        restful::revoke_access_token,
+        restful::stats
     ),
-    components(schemas(dto::NewWord, dto::Word, dto::TranslateResponse))
+    components(schemas(
+        dto::NewWord,
+        dto::UpdateWord,
+        dto::Word,
+        dto::AddedWord,
+        dto::TranslateResponse,
+        dto::TranslationCacheStats,
+        dto::DictionaryEntry,
+        dto::Sense,
+        dto::ReviewParams,
+        dto::ReviewSession,
+        dto::ReviewQueue,
+        dto::WordPage,
+        dto::Settings,
+        dto::UpdateSettings,
+        dto::Tag,
+        dto::Deck,
+        dto::NameParams,
+        dto::ImportedRow,
+        dto::ImportReport,
+        dto::DeletionReceipt,
+        dto::NewAccessToken,
+        dto::AccessToken,
+        dto::CreatedAccessToken,
+        dto::DailyCount,
+        dto::RecallScoreRetention,
+        dto::Stats
+    ))
 )]
 struct ApiDoc;
 
@@ -51,33 +117,22 @@ async fn main(
     pool: PgPool,
     #[shuttle_runtime::Secrets] secrets: SecretStore,
 ) -> ShuttleActixWeb<impl FnOnce(&mut ServiceConfig) + Send + Clone + 'static> {
-    let google_translate_api_key = secrets
-        .get("google_translate_api_key")
-        .expect("google translate api key was not found");
-
-    let cognito_region = secrets
-        .get("cognito_region")
-        .expect("cognito region was not found");
-
-    let cognito_user_pool_id = secrets
-        .get("cognito_user_pool_id")
-        .expect("cognito user pool id was not found");
-
-    let cognito_client_id = secrets
-        .get("cognito_client_id")
-        .expect("cognito client id was not found");
-
     setup_database(&pool)
         .await
         .expect("Failed to setup database");
 
-    let cognito_validator = Arc::new(Mutex::new(
-        cognito::CognitoValidator::new(&cognito_region, &cognito_user_pool_id, &cognito_client_id)
-            .await
-            .expect("Failed to create Cognito validator"),
+    let translator = Arc::new(CachedTranslator::new(
+        Arc::new(RetryingTranslator::new(create_translator(&secrets))),
+        pool.clone(),
     ));
+    let translation_cache_metrics = translator.metrics();
+    tokio::spawn(scheduled_purge_translation_cache(translator.clone()));
+
+    let dictionary: Arc<dyn Dictionary> = Arc::new(PostgresDictionary::new(pool.clone()));
 
-    tokio::spawn(scheduled_update_jwk(cognito_validator.clone()));
+    let oidc_validator = Arc::new(create_oidc_validator(&secrets).await);
+
+    tokio::spawn(scheduled_update_jwk(oidc_validator.clone()));
 
     let config = move |cfg: &mut ServiceConfig| {
         cfg.service(
@@ -85,17 +140,46 @@ async fn main(
         )
         .service(
             web::scope("/api/v1")
+                .wrap(from_fn(authorization::require_scope))
                 .wrap(Logger::default())
                 .wrap(HttpAuthentication::bearer(validator))
                 .service(retrieve)
                 .service(add)
+                .service(update)
+                .service(replace)
                 .service(list)
                 .service(delete)
                 .service(translate)
+                .service(translation_cache)
+                .service(lookup)
+                .service(review)
+                .service(history)
+                .service(queue)
+                .service(settings)
+                .service(update_settings)
+                .service(tags)
+                .service(add_tag)
+                .service(rename_tag)
+                .service(delete_tag)
+                .service(decks)
+                .service(add_deck)
+                .service(rename_deck)
+                .service(delete_deck)
+                .service(import)
+                .service(export)
+                .service(delete_account)
+                .service(delete_user)
+                .service(access_tokens)
+                .service(create_access_token)
+                .service(revoke_access_token)
+                .service(stats)
+                .app_data(web::PayloadConfig::new(engine::import::MAX_IMPORT_SIZE))
                 .app_data(Data::new(AppState {
                     pool: Arc::new(pool),
-                    cognito_validator: Some(cognito_validator),
-                    google_translate_api_key: google_translate_api_key.clone(),
+                    oidc_validator: Some(oidc_validator),
+                    translator: translator.clone(),
+                    translation_cache_metrics: translation_cache_metrics.clone(),
+                    dictionary: dictionary.clone(),
                 })),
         );
     };
@@ -103,31 +187,124 @@ async fn main(
     Ok(config.into())
 }
 
-async fn scheduled_update_jwk(cognito_validator: Arc<Mutex<cognito::CognitoValidator>>) {
+/// Creates the translation provider named by the `translator` secret
+///
+/// Google is used when no provider is named. The HTTP client is shared by all
+/// requests to the provider.
+fn create_translator(secrets: &SecretStore) -> Arc<dyn Translator> {
+    // Secrets that are not configured in the deployment come through as empty strings
+    let secret = |key: &str| secrets.get(key).filter(|value| !value.is_empty());
+    let client = reqwest::Client::builder()
+        .timeout(engine::translate::DEFAULT_TIMEOUT)
+        .build()
+        .expect("Failed to create HTTP client");
+
+    match secret("translator").as_deref().unwrap_or("google") {
+        "google" => Arc::new(GoogleTranslator::new(
+            client,
+            secret("google_translate_api_key").expect("google translate api key was not found"),
+        )),
+        "deepl" => Arc::new(DeepLTranslator::new(
+            client,
+            secret("deepl_api_key").expect("deepl api key was not found"),
+        )),
+        "libretranslate" => Arc::new(LibreTranslator::new(
+            client,
+            secret("libretranslate_url").expect("libretranslate url was not found"),
+            secret("libretranslate_api_key"),
+        )),
+        "fake" => Arc::new(FakeTranslator::new()),
+        provider => panic!("Unknown translator: {provider}"),
+    }
+}
+
+/// Creates the validator of the tokens of the OIDC provider named by the
+/// `oidc_issuer` secret
+///
+/// Without an issuer, the Cognito user pool named by the `cognito_region` and
+/// `cognito_user_pool_id` secrets is used. The ID of a user is read from the
+/// claim named by `oidc_user_id_claim`, one of `sub`, `username` or `email`,
+/// and defaults to `username`. `oidc_leeway_seconds` sets the clock skew
+/// allowed when checking the expiry of tokens.
+async fn create_oidc_validator(secrets: &SecretStore) -> oidc::OidcValidator {
+    // Secrets that are not configured in the deployment come through as empty strings
+    let secret = |key: &str| secrets.get(key).filter(|value| !value.is_empty());
+    let issuer = secret("oidc_issuer").unwrap_or_else(|| {
+        oidc::OidcValidator::cognito_issuer(
+            &secret("cognito_region").expect("cognito region was not found"),
+            &secret("cognito_user_pool_id").expect("cognito user pool id was not found"),
+        )
+    });
+    let client_id = secret("oidc_client_id").or_else(|| secret("cognito_client_id"));
+    let user_id_claim = secret("oidc_user_id_claim")
+        .map(|claim| claim.parse().expect("oidc user id claim is invalid"))
+        .unwrap_or_default();
+    let leeway = secret("oidc_leeway_seconds")
+        .map(|leeway| leeway.parse().expect("oidc leeway seconds is invalid"))
+        .unwrap_or(oidc::DEFAULT_LEEWAY);
+
+    oidc::OidcValidator::discover(&issuer, client_id.as_deref(), user_id_claim)
+        .await
+        .expect("Failed to create OIDC validator")
+        .with_leeway(leeway)
+}
+
+async fn scheduled_purge_translation_cache(translator: Arc<CachedTranslator>) {
     loop {
-        tokio::time::sleep(tokio::time::Duration::from_secs(3600 * 2)).await;
-        cognito_validator.lock().await.update_jwk().await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_secs(3600 * 24)).await;
+        if let Err(e) = translator.purge_expired().await {
+            tracing::error!("Failed to purge expired translations: {e}");
+        }
     }
 }
 
+/// Fetches the keys of the OIDC provider again whenever they expire
+///
+/// A failed fetch is retried with an increasing delay, while the previous keys
+/// are kept, so the task never stops.
+async fn scheduled_update_jwk(oidc_validator: Arc<oidc::OidcValidator>) {
+    const MIN_RETRY_DELAY: Duration = Duration::from_secs(10);
+    const MAX_RETRY_DELAY: Duration = Duration::from_secs(600);
+
+    let mut retry_delay = MIN_RETRY_DELAY;
+    loop {
+        tokio::time::sleep_until(oidc_validator.keys_expire_at()).await;
+        match oidc_validator.update_jwk().await {
+            Ok(()) => retry_delay = MIN_RETRY_DELAY,
+            Err(e) => {
+                tracing::warn!("Failed to update JWKS, retrying in {retry_delay:?}: {e}");
+                tokio::time::sleep(retry_delay).await;
+                retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+}
+
+/// Authenticates a request with a personal access token, or a token of the
+/// OIDC provider otherwise
 async fn validator(
     req: ServiceRequest,
     credentials: BearerAuth,
 ) -> Result<ServiceRequest, (actix_web::Error, ServiceRequest)> {
-    let cognito_validator = req
-        .app_data::<Data<AppState>>()
-        .unwrap()
-        .cognito_validator
-        .clone()
-        .unwrap();
-    let cognito_validator = cognito_validator.lock().await;
+    let state = req.app_data::<Data<AppState>>().unwrap().clone();
     let token = credentials.token();
-    match cognito_validator.validate_token(token) {
-        Ok(claims) => {
+    let claims = if token.starts_with(engine::tokens::TOKEN_PREFIX) {
+        match engine::tokens::authenticate_access_token(token, &state.pool).await {
+            Ok(owner) => owner.map(Claims::from),
+            Err(e) => return Err((e.into_actix_error(), req)),
+        }
+    } else {
+        match &state.oidc_validator {
+            Some(oidc_validator) => oidc_validator.validate_token(token).await.ok(),
+            None => None,
+        }
+    };
+    match claims {
+        Some(claims) => {
             req.extensions_mut().insert(claims);
             Ok(req)
         }
-        Err(_) => {
+        None => {
             let ae = AuthenticationError::new(Bearer::default());
             Err((actix_web::Error::from(ae), req))
         }
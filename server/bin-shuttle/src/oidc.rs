@@ -0,0 +1,903 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::header::CACHE_CONTROL;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// How long keys are kept when the provider does not say with Cache-Control
+const DEFAULT_JWKS_MAX_AGE: Duration = Duration::from_secs(3600 * 2);
+
+/// Bounds of how long keys are kept, whatever the provider says
+const MIN_JWKS_MAX_AGE: Duration = Duration::from_secs(60 * 5);
+const MAX_JWKS_MAX_AGE: Duration = Duration::from_secs(3600 * 24);
+
+/// Minimum time between fetches of the keys for tokens signed with an unknown key
+const MIN_JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Algorithms tokens may be signed with
+///
+/// Only asymmetric algorithms are accepted, so a token can't have itself
+/// verified with a shared secret made of a public key.
+const ALLOWED_ALGORITHMS: [Algorithm; 2] = [Algorithm::RS256, Algorithm::ES256];
+
+/// Default clock skew allowed when checking the expiry of a token, in seconds
+pub const DEFAULT_LEEWAY: u64 = 60;
+
+/// Timeout of requests to the provider
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The claim of a token the ID of the user is read from
+///
+/// Words, reviews and settings are stored under this ID, so it must not
+/// change once users have saved words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UserIdClaim {
+    Sub,
+    #[default]
+    Username, // `username`, or `preferred_username` or `cognito:username` if missing
+    Email,
+}
+
+impl UserIdClaim {
+    /// The names of the claim, in order of preference
+    fn names(&self) -> &'static [&'static str] {
+        match self {
+            UserIdClaim::Sub => &["sub"],
+            UserIdClaim::Username => &["username", "preferred_username", "cognito:username"],
+            UserIdClaim::Email => &["email"],
+        }
+    }
+}
+
+impl FromStr for UserIdClaim {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sub" => Ok(UserIdClaim::Sub),
+            "username" => Ok(UserIdClaim::Username),
+            "email" => Ok(UserIdClaim::Email),
+            _ => Err(format!("Unknown user ID claim: {s}")),
+        }
+    }
+}
+
+/// Errors of discovering the configuration of an OIDC provider
+#[derive(Debug, thiserror::Error)]
+pub enum DiscoveryError {
+    #[error("Failed to create HTTP client: {0}")]
+    Client(reqwest::Error),
+    #[error("Failed to fetch {0}: {1}")]
+    Fetch(String, reqwest::Error),
+    #[error("Issuer {found} does not match the configured issuer {expected}")]
+    IssuerMismatch { expected: String, found: String },
+}
+
+/// Validates tokens issued by an OpenID Connect provider, such as Amazon
+/// Cognito or Keycloak
+///
+/// Tokens are validated against the current keys without locking, while the
+/// keys are replaced as a whole when they are fetched again.
+pub struct OidcValidator {
+    issuer: String,
+    audience: Option<String>,
+    user_id_claim: UserIdClaim,
+    leeway: u64,
+    jwks_uri: String,
+    client: reqwest::Client,
+    keys: ArcSwap<KeySet>,
+    last_refresh: Mutex<Instant>, // Held while the keys are fetched, so only one fetch runs at a time
+}
+
+/// The keys of a provider and when they should be fetched again
+struct KeySet {
+    jwks: Jwks,
+    expires_at: Instant,
+}
+
+impl OidcValidator {
+    /// The issuer URL of an Amazon Cognito user pool
+    pub fn cognito_issuer(region: &str, user_pool_id: &str) -> String {
+        format!(
+            "https://cognito-idp.{}.amazonaws.com/{}",
+            region, user_pool_id
+        )
+    }
+
+    /// Creates a validator from the discovery document of a provider
+    ///
+    /// # Arguments
+    ///
+    /// * `issuer` - The issuer URL, whose `/.well-known/openid-configuration` names the JWKS
+    /// * `audience` - The client ID tokens must be issued to, or `None` to accept any
+    /// * `user_id_claim` - The claim the ID of the user is read from
+    pub async fn discover(
+        issuer: &str,
+        audience: Option<&str>,
+        user_id_claim: UserIdClaim,
+    ) -> Result<Self, DiscoveryError> {
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        // The keys are fetched minutes apart, after the provider has closed an idle connection
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .pool_max_idle_per_host(0)
+            .build()
+            .map_err(DiscoveryError::Client)?;
+        let configuration: ProviderConfiguration = fetch_json(&client, &discovery_url).await?.0;
+        if configuration.issuer.trim_end_matches('/') != issuer.trim_end_matches('/') {
+            return Err(DiscoveryError::IssuerMismatch {
+                expected: issuer.to_string(),
+                found: configuration.issuer,
+            });
+        }
+        let keys = fetch_keys(&client, &configuration.jwks_uri).await?;
+        Ok(Self {
+            // Tokens carry the issuer exactly as the provider spells it
+            issuer: configuration.issuer,
+            audience: audience.map(str::to_string),
+            user_id_claim,
+            leeway: DEFAULT_LEEWAY,
+            jwks_uri: configuration.jwks_uri,
+            client,
+            keys: ArcSwap::from_pointee(keys),
+            last_refresh: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Sets the clock skew allowed when checking the expiry of tokens, in seconds
+    pub fn with_leeway(mut self, leeway: u64) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Validates a token and reads its claims
+    ///
+    /// Both access and ID tokens are accepted. A token signed with a key that
+    /// is not known yet makes the keys be fetched again, at most once every
+    /// `MIN_JWKS_REFRESH_INTERVAL`, as the provider may have rotated its keys.
+    pub async fn validate_token(&self, token: &str) -> Result<Claims, Box<dyn Error>> {
+        // Decode the header to get the key id (kid)
+        let header = decode_header(token)?;
+        let alg = header.alg;
+        if !ALLOWED_ALGORITHMS.contains(&alg) {
+            return Err(format!("Algorithm {alg:?} is not allowed").into());
+        }
+        let kid = header.kid.ok_or("Token has no key ID")?;
+
+        // Find the corresponding JWK
+        let jwk = match self.find_key(&kid) {
+            Some(jwk) => jwk,
+            None => {
+                self.refresh_for_unknown_key(&kid).await?;
+                self.find_key(&kid)
+                    .ok_or("Can't find the corresponding JWK")?
+            }
+        };
+
+        // Convert the JWK to a decoding key
+        let decoding_key = jwk.decoding_key(alg)?;
+
+        // Define validation criteria
+        let mut validation = Validation::new(alg);
+        validation.leeway = self.leeway;
+        validation.validate_nbf = true;
+        // Checked with the token use, as Cognito access tokens have no audience
+        validation.validate_aud = false;
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss"]);
+
+        // Decode and validate the token
+        let token_data = decode::<HashMap<String, Value>>(token, &decoding_key, &validation)?;
+        let claims = token_data.claims;
+        self.check_audience(&claims)?;
+
+        let user_id = self
+            .user_id_claim
+            .names()
+            .iter()
+            .find_map(|name| claims.get(*name).and_then(Value::as_str))
+            .ok_or("Token has no user ID claim")?;
+        Ok(Claims {
+            exp: claims
+                .get("exp")
+                .and_then(Value::as_u64)
+                .ok_or("Token has no expiry")? as usize,
+            user_id: user_id.to_string(),
+            scopes: scopes(&claims),
+            groups: string_list(claims.get("cognito:groups").or(claims.get("groups"))),
+            token_id: None,
+        })
+    }
+
+    /// Checks that a token was issued to the configured client
+    ///
+    /// Cognito tells access tokens from ID tokens with `token_use`, and names
+    /// the client in `client_id` of access tokens and `aud` of ID tokens.
+    /// Tokens of other providers name it in `aud`, or in `azp` for access
+    /// tokens issued to an API.
+    fn check_audience(&self, claims: &HashMap<String, Value>) -> Result<(), Box<dyn Error>> {
+        let token_use = match claims.get("token_use") {
+            None => None,
+            Some(Value::String(token_use)) if token_use == "access" || token_use == "id" => {
+                Some(token_use.as_str())
+            }
+            Some(_) => return Err("Token is neither an access nor an ID token".into()),
+        };
+        let Some(audience) = &self.audience else {
+            return Ok(());
+        };
+
+        let claim = |name: &str| claims.get(name).and_then(Value::as_str);
+        let in_aud = match claims.get("aud") {
+            Some(Value::String(aud)) => aud == audience,
+            Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+            _ => false,
+        };
+        let issued_to_client = match token_use {
+            Some("access") => claim("client_id") == Some(audience),
+            Some(_) => in_aud,
+            None => in_aud || claim("azp") == Some(audience),
+        };
+        if issued_to_client {
+            Ok(())
+        } else {
+            Err("Token was issued to another client".into())
+        }
+    }
+
+    /// Fetches the keys of the provider again
+    pub async fn update_jwk(&self) -> Result<(), DiscoveryError> {
+        let mut last_refresh = self.last_refresh.lock().await;
+        *last_refresh = Instant::now();
+        self.keys
+            .store(Arc::new(fetch_keys(&self.client, &self.jwks_uri).await?));
+        Ok(())
+    }
+
+    /// When the keys should be fetched again, as told by the provider's Cache-Control
+    pub fn keys_expire_at(&self) -> Instant {
+        self.keys.load().expires_at
+    }
+
+    fn find_key(&self, kid: &str) -> Option<Jwk> {
+        self.keys
+            .load()
+            .jwks
+            .keys
+            .iter()
+            .find(|key| key.kid.as_deref() == Some(kid))
+            .cloned()
+    }
+
+    async fn refresh_for_unknown_key(&self, kid: &str) -> Result<(), DiscoveryError> {
+        let mut last_refresh = self.last_refresh.lock().await;
+        // The keys may have been fetched while waiting for the lock
+        if self.find_key(kid).is_some() || last_refresh.elapsed() < MIN_JWKS_REFRESH_INTERVAL {
+            return Ok(());
+        }
+        *last_refresh = Instant::now();
+        self.keys
+            .store(Arc::new(fetch_keys(&self.client, &self.jwks_uri).await?));
+        Ok(())
+    }
+}
+
+async fn fetch_keys(client: &reqwest::Client, jwks_uri: &str) -> Result<KeySet, DiscoveryError> {
+    let (jwks, cache_control) = fetch_json(client, jwks_uri).await?;
+    Ok(KeySet {
+        jwks,
+        expires_at: Instant::now() + max_age(cache_control.as_deref()),
+    })
+}
+
+/// Fetches a JSON document along with its Cache-Control header
+async fn fetch_json<T: for<'de> Deserialize<'de>>(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<(T, Option<String>), DiscoveryError> {
+    let fetch = async {
+        let response = client.get(url).send().await?.error_for_status()?;
+        let cache_control = response
+            .headers()
+            .get(CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Ok((response.json::<T>().await?, cache_control))
+    };
+    fetch
+        .await
+        .map_err(|e| DiscoveryError::Fetch(url.to_string(), e))
+}
+
+/// How long a response may be kept, from its Cache-Control header, within
+/// `MIN_JWKS_MAX_AGE` and `MAX_JWKS_MAX_AGE`
+fn max_age(cache_control: Option<&str>) -> Duration {
+    let Some(cache_control) = cache_control else {
+        return DEFAULT_JWKS_MAX_AGE;
+    };
+    let mut max_age = DEFAULT_JWKS_MAX_AGE;
+    for directive in cache_control.split(',').map(str::trim) {
+        let directive = directive.to_ascii_lowercase();
+        if directive == "no-cache" || directive == "no-store" {
+            return MIN_JWKS_MAX_AGE;
+        }
+        if let Some(seconds) = directive
+            .strip_prefix("max-age=")
+            .and_then(|seconds| seconds.trim_matches('"').parse().ok())
+        {
+            max_age = Duration::from_secs(seconds);
+        }
+    }
+    max_age.clamp(MIN_JWKS_MAX_AGE, MAX_JWKS_MAX_AGE)
+}
+
+/// The scopes of a token, space-separated in `scope` or listed in `scp` as some providers do
+fn scopes(claims: &HashMap<String, Value>) -> Vec<String> {
+    match claims.get("scope") {
+        Some(Value::String(scope)) => scope.split_whitespace().map(str::to_string).collect(),
+        _ => string_list(claims.get("scp")),
+    }
+}
+
+/// The strings of a claim listing them, or of a claim with a single one
+fn string_list(claim: Option<&Value>) -> Vec<String> {
+    match claim {
+        Some(Value::String(value)) => vec![value.clone()],
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The claims of a validated token
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Claims {
+    pub exp: usize,
+    pub user_id: String, // The value of the configured user ID claim
+    pub scopes: Vec<String>,
+    pub groups: Vec<String>, // `cognito:groups`, or `groups` of other providers
+    pub token_id: Option<i32>, // The personal access token the request was authenticated with
+}
+
+/// Create claims from engine::tokens::TokenOwner
+impl From<engine::tokens::TokenOwner> for Claims {
+    fn from(owner: engine::tokens::TokenOwner) -> Self {
+        Self {
+            exp: owner.expires_at.and_utc().timestamp() as usize,
+            user_id: owner.user_id,
+            scopes: owner.scopes,
+            groups: Vec::new(),
+            token_id: Some(owner.token_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderConfiguration {
+    issuer: String,
+    jwks_uri: String,
+}
+
+/// A public key of a provider, of which only signing keys of allowed algorithms are used
+#[derive(Debug, Clone, Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    alg: Option<String>,
+    #[serde(rename = "use")]
+    key_use: Option<String>,
+    n: Option<String>, // RSA modulus
+    e: Option<String>, // RSA exponent
+    crv: Option<String>,
+    x: Option<String>, // EC coordinates
+    y: Option<String>,
+}
+
+impl Jwk {
+    /// The key to verify a token signed with the algorithm, if the key is meant for it
+    fn decoding_key(&self, alg: Algorithm) -> Result<DecodingKey, Box<dyn Error>> {
+        if self
+            .key_use
+            .as_deref()
+            .is_some_and(|key_use| key_use != "sig")
+        {
+            return Err("Key is not a signing key".into());
+        }
+        if let Some(key_alg) = &self.alg {
+            if key_alg.parse::<Algorithm>().ok() != Some(alg) {
+                return Err(format!("Key is for {key_alg}, not {alg:?}").into());
+            }
+        }
+        let missing = "Key is missing a public key parameter";
+        let key = match (alg, self.kty.as_str(), self.crv.as_deref()) {
+            (Algorithm::RS256, "RSA", _) => DecodingKey::from_rsa_components(
+                self.n.as_deref().ok_or(missing)?,
+                self.e.as_deref().ok_or(missing)?,
+            )?,
+            (Algorithm::ES256, "EC", Some("P-256")) => DecodingKey::from_ec_components(
+                self.x.as_deref().ok_or(missing)?,
+                self.y.as_deref().ok_or(missing)?,
+            )?,
+            _ => return Err(format!("{} key does not match {alg:?}", self.kty).into()),
+        };
+        Ok(key)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[cfg(test)]
+pub mod test_issuer {
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, RwLock};
+
+    use actix_web::http::header::CACHE_CONTROL;
+
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+    use rsa::pkcs1::EncodeRsaPrivateKey;
+    use rsa::traits::PublicKeyParts;
+    use rsa::RsaPrivateKey;
+    use serde_json::{json, Value};
+
+    /// Cache-Control of the JWKS
+    pub const JWKS_CACHE_CONTROL: &str = "public, max-age=600";
+
+    /// The ID of the P-256 key of the provider
+    pub const EC_KID: &str = "test-ec-key";
+
+    /// A local OIDC provider serving discovery and a JWKS, and signing tokens
+    /// with an RSA key, which can be rotated, or a P-256 key
+    pub struct TestIssuer {
+        pub issuer: String,
+        kid: String,
+        encoding_key: EncodingKey,
+        ec_encoding_key: EncodingKey,
+        jwks: Arc<RwLock<Value>>,
+        jwks_requests: Arc<AtomicUsize>,
+    }
+
+    impl TestIssuer {
+        /// Starts the provider on a free local port
+        pub fn start() -> Self {
+            let jwks = Arc::new(RwLock::new(json!({ "keys": [] })));
+            let jwks_requests = Arc::new(AtomicUsize::new(0));
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let issuer = format!("http://{}/realms/test", listener.local_addr().unwrap());
+            let configuration = json!({
+                "issuer": issuer,
+                "jwks_uri": format!("{issuer}/protocol/openid-connect/certs"),
+            });
+            let server = {
+                let jwks = jwks.clone();
+                let jwks_requests = jwks_requests.clone();
+                HttpServer::new(move || {
+                    let configuration = configuration.clone();
+                    let jwks = jwks.clone();
+                    let jwks_requests = jwks_requests.clone();
+                    App::new()
+                        .route(
+                            "/realms/test/.well-known/openid-configuration",
+                            web::get().to(move || {
+                                let configuration = configuration.clone();
+                                async move { HttpResponse::Ok().json(configuration) }
+                            }),
+                        )
+                        .route(
+                            "/realms/test/protocol/openid-connect/certs",
+                            web::get().to(move || {
+                                jwks_requests.fetch_add(1, Ordering::SeqCst);
+                                let jwks = jwks.read().unwrap().clone();
+                                async move {
+                                    HttpResponse::Ok()
+                                        .insert_header((CACHE_CONTROL, JWKS_CACHE_CONTROL))
+                                        .json(jwks)
+                                }
+                            }),
+                        )
+                })
+                .workers(1)
+                .listen(listener)
+                .unwrap()
+                .run()
+            };
+            actix_web::rt::spawn(server);
+
+            let ec_encoding_key = add_ec_key(&mut jwks.write().unwrap());
+            let (kid, encoding_key) = add_key(&mut jwks.write().unwrap());
+            Self {
+                issuer,
+                kid,
+                encoding_key,
+                ec_encoding_key,
+                jwks,
+                jwks_requests,
+            }
+        }
+
+        /// Signs the following tokens with a new key, served alongside the previous ones
+        pub fn rotate_key(&mut self) {
+            (self.kid, self.encoding_key) = add_key(&mut self.jwks.write().unwrap());
+        }
+
+        /// The number of times the JWKS was fetched
+        pub fn jwks_requests(&self) -> usize {
+            self.jwks_requests.load(Ordering::SeqCst)
+        }
+
+        /// The JWK of the RSA key tokens are signed with
+        pub fn jwk(&self) -> Value {
+            let jwks = self.jwks.read().unwrap();
+            let keys = jwks["keys"].as_array().unwrap();
+            keys.iter()
+                .find(|key| key["kid"] == self.kid)
+                .unwrap()
+                .clone()
+        }
+
+        /// Serves another key in the JWKS
+        pub fn serve_jwk(&self, jwk: Value) {
+            let mut jwks = self.jwks.write().unwrap();
+            jwks["keys"].as_array_mut().unwrap().push(jwk);
+        }
+
+        /// Signs a token with the claims with the RSA key
+        pub fn sign(&self, claims: Value) -> String {
+            let mut header = Header::new(Algorithm::RS256);
+            header.kid = Some(self.kid.clone());
+            self.sign_with(header, claims)
+        }
+
+        /// Signs a token with the claims with the P-256 key
+        pub fn sign_es256(&self, claims: Value) -> String {
+            let mut header = Header::new(Algorithm::ES256);
+            header.kid = Some(EC_KID.to_string());
+            self.sign_with(header, claims)
+        }
+
+        /// Signs a token with the header and claims, adding `iss` and an `exp`
+        /// an hour from now
+        ///
+        /// ES256 tokens are signed with the P-256 key, others with the RSA key.
+        pub fn sign_with(&self, header: Header, mut claims: Value) -> String {
+            claims["iss"] = json!(self.issuer);
+            if claims.get("exp").is_none() {
+                claims["exp"] = json!(chrono::Utc::now().timestamp() + 3600);
+            }
+            let key = match header.alg {
+                Algorithm::ES256 => &self.ec_encoding_key,
+                _ => &self.encoding_key,
+            };
+            encode(&header, &claims, key).unwrap()
+        }
+    }
+
+    /// Generates a key and adds it to the JWKS, returning its ID and signing key
+    fn add_key(jwks: &mut Value) -> (String, EncodingKey) {
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap();
+        let kid = format!("test-key-{}", jwks["keys"].as_array().unwrap().len());
+        jwks["keys"].as_array_mut().unwrap().push(json!({
+            "kty": "RSA",
+            "alg": "RS256",
+            "use": "sig",
+            "kid": kid,
+            "n": URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+            "e": URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+        }));
+
+        let pem = key.to_pkcs1_pem(rsa::pkcs1::LineEnding::LF).unwrap();
+        (kid, EncodingKey::from_rsa_pem(pem.as_bytes()).unwrap())
+    }
+
+    /// Generates a P-256 key and adds it to the JWKS, returning its signing key
+    fn add_ec_key(jwks: &mut Value) -> EncodingKey {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+                .unwrap();
+        // An uncompressed point, 0x04 followed by the coordinates
+        let point = key_pair.public_key().as_ref();
+        jwks["keys"].as_array_mut().unwrap().push(json!({
+            "kty": "EC",
+            "alg": "ES256",
+            "use": "sig",
+            "kid": EC_KID,
+            "crv": "P-256",
+            "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+            "y": URL_SAFE_NO_PAD.encode(&point[33..]),
+        }));
+
+        EncodingKey::from_ec_der(pkcs8.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_issuer::TestIssuer;
+    use super::*;
+    use jsonwebtoken::Header;
+    use serde_json::json;
+
+    #[ignore = "requires valid token"]
+    #[tokio::test]
+    async fn test_cognito_validator() {
+        let toml = crate::test_utils::get_secrets().await;
+
+        let validator = OidcValidator::discover(
+            &OidcValidator::cognito_issuer(&toml.cognito_region, &toml.cognito_user_pool_id),
+            Some(&toml.cognito_client_id),
+            UserIdClaim::Username,
+        )
+        .await
+        .unwrap();
+
+        let token = "eyJraWQiOiJzTEY0dDBVb05QZmNDY1J1QXZENHVRRWx2bGxVWndBR1I3S0hLVVhpM3pFPSIsImFsZyI6IlJTMjU2In0.eyJzdWIiOiJkNzU1OGQ0Yi0zNGM1LTQyZTEtODVlMi0zOTRhNmFlMDNjZDYiLCJpc3MiOiJodHRwczpcL1wvY29nbml0by1pZHAudXMtZWFzdC0xLmFtYXpvbmF3cy5jb21cL3VzLWVhc3QtMV9RYnppOWx2VkIiLCJ2ZXJzaW9uIjoyLCJjbGllbnRfaWQiOiI1cDk5czVubDduaGE1dGZucGlrM3IwcmI3aiIsIm9yaWdpbl9qdGkiOiI5ZTM2NjhlNC1mYzQzLTQxNTktYThlMi1mZDViNzEyNDgzYjMiLCJ0b2tlbl91c2UiOiJhY2Nlc3MiLCJzY29wZSI6ImF3cy5jb2duaXRvLnNpZ25pbi51c2VyLmFkbWluIG9wZW5pZCBwcm9maWxlIiwiYXV0aF90aW1lIjoxNzIxNDYyNjQzLCJleHAiOjE3MjE0NjYyNDMsImlhdCI6MTcyMTQ2MjY0MywianRpIjoiNjIxYTcyNWEtMWZiZC00NzIyLTg4YzQtZDk4NTBlYWUwNzcwIiwidXNlcm5hbWUiOiJtaWd1b2xpYW5nIn0.rVtHAWfpZr5-oIswCHbpHGeUzAzxQwFbgIjDEjAmA7tvaRDticn95n1amWt0B_946EgN_HyTMkQ6YRX1Muifu15Q60Y3yxDcZ0qG2UAMqthgf-XmyPPd4l9BfadufDzxDvGLan4TC81_OAZQyW6tui7_lQwAI71vf2DNcJQMuXJJkzFSftX0dQURs3mi9Uzn6kf44IWj_RLKHkJDFuBmiOuwENx2AvzGHla9J-VHDmv29Qr63NN6o2Squ1RiRmLmO0UTsnUuqlB1bVf2AE47ZsneISFCPbbbmJSH7P7qYYi35_wEDjCLd2B53yXrSOco0WRFcFlXdprfh2KAu2mIgg";
+
+        assert!(validator.validate_token(token).await.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn test_oidc_validator() {
+        let issuer = TestIssuer::start();
+        let claims = json!({
+            "sub": "0b1c2d3e-4f5a-6b7c-8d9e-0f1a2b3c4d5e",
+            "preferred_username": "keycloak_user",
+            "email": "user@example.com",
+            "aud": "test_client",
+        });
+
+        for (user_id_claim, user_id) in [
+            (UserIdClaim::Sub, "0b1c2d3e-4f5a-6b7c-8d9e-0f1a2b3c4d5e"),
+            (UserIdClaim::Username, "keycloak_user"),
+            (UserIdClaim::Email, "user@example.com"),
+        ] {
+            let validator =
+                OidcValidator::discover(&issuer.issuer, Some("test_client"), user_id_claim)
+                    .await
+                    .unwrap();
+            let validated = validator.validate_token(&issuer.sign(claims.clone())).await;
+            assert_eq!(validated.unwrap().user_id, user_id);
+        }
+
+        let validator =
+            OidcValidator::discover(&issuer.issuer, Some("test_client"), UserIdClaim::Username)
+                .await
+                .unwrap();
+        // Scopes and groups
+        let token = issuer.sign(json!({
+            "username": "test_user",
+            "aud": "test_client",
+            "scope": "openid read",
+            "cognito:groups": ["admin"],
+        }));
+        let claims = validator.validate_token(&token).await.unwrap();
+        assert_eq!(claims.scopes, vec!["openid", "read"]);
+        assert_eq!(claims.groups, vec!["admin"]);
+        let token = issuer.sign(json!({
+            "username": "test_user",
+            "aud": "test_client",
+            "scp": ["write"],
+            "groups": "readers",
+        }));
+        let claims = validator.validate_token(&token).await.unwrap();
+        assert_eq!(claims.scopes, vec!["write"]);
+        assert_eq!(claims.groups, vec!["readers"]);
+
+        // Issued to another client
+        let token = issuer.sign(json!({ "username": "test_user", "aud": "other_client" }));
+        assert!(validator.validate_token(&token).await.is_err());
+        // Expired
+        let token = issuer.sign(json!({ "username": "test_user", "aud": "test_client", "exp": 1 }));
+        assert!(validator.validate_token(&token).await.is_err());
+        // Without the user ID claim
+        let token = issuer.sign(json!({ "sub": "test_user", "aud": "test_client" }));
+        assert!(validator.validate_token(&token).await.is_err());
+
+        // Discovery rejects a provider that names itself another issuer
+        let result = OidcValidator::discover(
+            &issuer.issuer.replace("127.0.0.1", "localhost"),
+            None,
+            UserIdClaim::Sub,
+        )
+        .await;
+        assert!(matches!(result, Err(DiscoveryError::IssuerMismatch { .. })));
+    }
+
+    #[actix_web::test]
+    async fn test_jwks_rotation() {
+        let mut issuer = TestIssuer::start();
+        let validator = OidcValidator::discover(&issuer.issuer, None, UserIdClaim::Sub)
+            .await
+            .unwrap();
+        assert_eq!(issuer.jwks_requests(), 1);
+        // The keys are kept as long as the provider's Cache-Control says
+        let max_age = validator.keys_expire_at() - Instant::now();
+        assert!(max_age > Duration::from_secs(590) && max_age <= Duration::from_secs(600));
+
+        // A key rotated right after the keys were fetched is not fetched on demand
+        issuer.rotate_key();
+        let token = issuer.sign(json!({ "sub": "test_user" }));
+        assert!(validator.validate_token(&token).await.is_err());
+        assert_eq!(issuer.jwks_requests(), 1);
+
+        // Once the refresh interval has passed, it is
+        *validator.last_refresh.lock().await -= MIN_JWKS_REFRESH_INTERVAL;
+        assert!(validator.validate_token(&token).await.is_ok());
+        assert_eq!(issuer.jwks_requests(), 2);
+        // Known keys are not fetched again
+        assert!(validator.validate_token(&token).await.is_ok());
+        assert_eq!(issuer.jwks_requests(), 2);
+
+        // The scheduled update fetches the keys whatever the interval
+        issuer.rotate_key();
+        validator.update_jwk().await.unwrap();
+        let token = issuer.sign(json!({ "sub": "test_user" }));
+        assert!(validator.validate_token(&token).await.is_ok());
+        assert_eq!(issuer.jwks_requests(), 3);
+    }
+
+    #[actix_web::test]
+    async fn test_token_signatures() {
+        let issuer = TestIssuer::start();
+        let validator = OidcValidator::discover(&issuer.issuer, None, UserIdClaim::Sub)
+            .await
+            .unwrap();
+        let claims = json!({ "sub": "test_user" });
+
+        assert!(validator
+            .validate_token(&issuer.sign(claims.clone()))
+            .await
+            .is_ok());
+        assert!(validator
+            .validate_token(&issuer.sign_es256(claims.clone()))
+            .await
+            .is_ok());
+
+        // An algorithm outside the allow-list, even with a valid signature
+        let mut header = Header::new(Algorithm::RS384);
+        header.kid = Some(issuer.jwk()["kid"].as_str().unwrap().to_string());
+        let token = issuer.sign_with(header.clone(), claims.clone());
+        assert!(validator.validate_token(&token).await.is_err());
+        // A shared secret made of the public key
+        header.alg = Algorithm::HS256;
+        let secret = issuer.jwk()["n"].as_str().unwrap().to_string();
+        let token = jsonwebtoken::encode(
+            &header,
+            &json!({ "sub": "test_user", "iss": issuer.issuer, "exp": u32::MAX }),
+            &jsonwebtoken::EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap();
+        assert!(validator.validate_token(&token).await.is_err());
+        // Without a key ID
+        let token = issuer.sign_with(Header::new(Algorithm::RS256), claims.clone());
+        assert!(validator.validate_token(&token).await.is_err());
+        // An allowed algorithm that is not the one of the key
+        header.alg = Algorithm::ES256;
+        let token = issuer.sign_with(header, claims.clone());
+        assert!(validator.validate_token(&token).await.is_err());
+
+        // A key for encryption, or without an algorithm
+        let mut encryption_jwk = issuer.jwk();
+        encryption_jwk["kid"] = json!("test-encryption-key");
+        encryption_jwk["use"] = json!("enc");
+        let mut unnamed_jwk = issuer.jwk();
+        unnamed_jwk["kid"] = json!("test-unnamed-key");
+        unnamed_jwk.as_object_mut().unwrap().remove("alg");
+        issuer.serve_jwk(encryption_jwk);
+        issuer.serve_jwk(unnamed_jwk);
+        validator.update_jwk().await.unwrap();
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("test-encryption-key".to_string());
+        let token = issuer.sign_with(header.clone(), claims.clone());
+        assert!(validator.validate_token(&token).await.is_err());
+        header.kid = Some("test-unnamed-key".to_string());
+        let token = issuer.sign_with(header, claims.clone());
+        assert!(validator.validate_token(&token).await.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn test_token_use_and_audience() {
+        let issuer = TestIssuer::start();
+        let validator =
+            OidcValidator::discover(&issuer.issuer, Some("test_client"), UserIdClaim::Sub)
+                .await
+                .unwrap();
+
+        for (claims, valid) in [
+            // Cognito access tokens name the client in `client_id`
+            (
+                json!({ "token_use": "access", "client_id": "test_client" }),
+                true,
+            ),
+            (
+                json!({ "token_use": "access", "client_id": "other_client" }),
+                false,
+            ),
+            (
+                json!({ "token_use": "access", "aud": "test_client" }),
+                false,
+            ),
+            // ID tokens in `aud`
+            (json!({ "token_use": "id", "aud": "test_client" }), true),
+            (
+                json!({ "token_use": "id", "aud": ["api", "test_client"] }),
+                true,
+            ),
+            (
+                json!({ "token_use": "id", "client_id": "test_client" }),
+                false,
+            ),
+            (
+                json!({ "token_use": "refresh", "aud": "test_client" }),
+                false,
+            ),
+            // Other providers in `aud`, or `azp` for tokens issued to an API
+            (json!({ "aud": "test_client" }), true),
+            (json!({ "aud": "account", "azp": "test_client" }), true),
+            (json!({ "aud": "account", "azp": "other_client" }), false),
+            (json!({}), false),
+        ] {
+            let mut claims = claims;
+            claims["sub"] = json!("test_user");
+            let result = validator.validate_token(&issuer.sign(claims.clone())).await;
+            assert_eq!(result.is_ok(), valid, "{claims}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_leeway() {
+        let issuer = TestIssuer::start();
+        let validator = OidcValidator::discover(&issuer.issuer, None, UserIdClaim::Sub)
+            .await
+            .unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let expired = issuer.sign(json!({ "sub": "test_user", "exp": now - 30 }));
+        let not_yet_valid = issuer.sign(json!({ "sub": "test_user", "nbf": now + 30 }));
+        let long_expired = issuer.sign(json!({ "sub": "test_user", "exp": now - 300 }));
+
+        assert!(validator.validate_token(&expired).await.is_ok());
+        assert!(validator.validate_token(&not_yet_valid).await.is_ok());
+        assert!(validator.validate_token(&long_expired).await.is_err());
+
+        let validator = validator.with_leeway(0);
+        assert!(validator.validate_token(&expired).await.is_err());
+        assert!(validator.validate_token(&not_yet_valid).await.is_err());
+    }
+
+    #[test]
+    fn test_max_age() {
+        assert_eq!(max_age(None), DEFAULT_JWKS_MAX_AGE);
+        assert_eq!(
+            max_age(Some("public, max-age=3600")),
+            Duration::from_secs(3600)
+        );
+        assert_eq!(max_age(Some("max-age=1")), MIN_JWKS_MAX_AGE);
+        assert_eq!(max_age(Some("Max-Age=31536000")), MAX_JWKS_MAX_AGE);
+        assert_eq!(max_age(Some("no-cache")), MIN_JWKS_MAX_AGE);
+        assert_eq!(max_age(Some("private")), DEFAULT_JWKS_MAX_AGE);
+    }
+}
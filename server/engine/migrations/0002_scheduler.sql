@@ -0,0 +1,10 @@
+ALTER TABLE review_sessions
+    ADD COLUMN repetitions INT NOT NULL DEFAULT 0, -- Consecutive successful reviews
+    ADD COLUMN ease_factor FLOAT NOT NULL DEFAULT 2.5, -- SM-2 ease factor
+    ADD COLUMN stability FLOAT NOT NULL DEFAULT 0, -- FSRS stability in days
+    ADD COLUMN difficulty FLOAT NOT NULL DEFAULT 0; -- FSRS difficulty between 1 and 10
+
+CREATE TABLE user_settings (
+    user_id VARCHAR(255) PRIMARY KEY, -- JWT 'username' field
+    scheduler VARCHAR(16) NOT NULL DEFAULT 'sm2' -- Spaced-repetition algorithm
+);
@@ -0,0 +1,20 @@
+-- Each word has a single forgetting curve, updated after every review
+ALTER TABLE forgetting_curve ADD CONSTRAINT forgetting_curve_word_id_key UNIQUE (word_id);
+
+-- Predicted probability of recalling each word right now, following
+-- retention_rate ^ days since the last review. Words that have never been
+-- reviewed start from their initial forgetting rate.
+CREATE VIEW word_retention AS
+SELECT
+    words.word_id,
+    POWER(
+        COALESCE(forgetting_curve.retention_rate, 1 - words.initial_forgetting_rate),
+        GREATEST(EXTRACT(EPOCH FROM ((NOW() AT TIME ZONE 'UTC') - COALESCE(last_review.review_date, words.date_added))) / 86400, 0)
+    )::FLOAT8 AS retention
+FROM words
+LEFT JOIN forgetting_curve USING (word_id)
+LEFT JOIN LATERAL (
+    SELECT MAX(review_date) AS review_date
+    FROM review_sessions
+    WHERE review_sessions.word_id = words.word_id
+) AS last_review ON TRUE;
@@ -0,0 +1,23 @@
+ALTER TABLE user_settings
+    ADD COLUMN new_words_per_day INT NOT NULL DEFAULT 20, -- New words introduced per day
+    ADD COLUMN max_reviews_per_day INT NOT NULL DEFAULT 200; -- Reviews of known words per day
+
+-- When each word is due next, and whether it has ever been reviewed. The
+-- session created along with a word carries no recall score.
+CREATE VIEW word_schedule AS
+SELECT
+    words.word_id,
+    latest_session.next_review_date,
+    NOT EXISTS (
+        SELECT 1
+        FROM review_sessions
+        WHERE review_sessions.word_id = words.word_id AND recall_score IS NOT NULL
+    ) AS is_new
+FROM words
+LEFT JOIN LATERAL (
+    SELECT next_review_date
+    FROM review_sessions
+    WHERE review_sessions.word_id = words.word_id
+    ORDER BY review_date DESC, session_id DESC
+    LIMIT 1
+) AS latest_session ON TRUE;
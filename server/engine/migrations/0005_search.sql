@@ -0,0 +1,9 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- The 'simple' configuration does not stem, so it works for any language
+ALTER TABLE words ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', word), 'A') || setweight(to_tsvector('simple', definition), 'B')
+) STORED;
+
+CREATE INDEX words_search_vector_idx ON words USING GIN (search_vector);
+CREATE INDEX words_word_trgm_idx ON words USING GIN (lower(word) gin_trgm_ops);
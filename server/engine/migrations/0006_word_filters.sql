@@ -0,0 +1,6 @@
+-- Host name of the page a word was saved from, for filtering by domain
+ALTER TABLE words ADD COLUMN url_host VARCHAR(5000) GENERATED ALWAYS AS (
+    lower(substring(url FROM '^[a-zA-Z][a-zA-Z0-9+.-]*://(?:[^@/?#]*@)?([^:/?#]+)'))
+) STORED;
+
+CREATE INDEX words_user_id_url_host_idx ON words (user_id, url_host);
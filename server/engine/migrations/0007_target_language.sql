@@ -0,0 +1,2 @@
+ALTER TABLE user_settings
+    ADD COLUMN target_language VARCHAR(35) NOT NULL DEFAULT 'zh'; -- BCP-47 tag words are translated into by default
@@ -0,0 +1,15 @@
+-- Translations reused across requests and instances, keyed by the
+-- normalized text, in its original case, language pair and provider
+CREATE TABLE translation_cache (
+    provider VARCHAR(32) NOT NULL,
+    source_language VARCHAR(35) NOT NULL, -- 'auto' when the provider detected it
+    target_language VARCHAR(35) NOT NULL,
+    text VARCHAR(5000) NOT NULL,
+    text_hash CHAR(32) GENERATED ALWAYS AS (md5(text)) STORED, -- Long texts do not fit in an index
+    translation TEXT NOT NULL,
+    detected_language VARCHAR(35),
+    created_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'UTC'),
+    PRIMARY KEY (provider, source_language, target_language, text_hash)
+);
+
+CREATE INDEX translation_cache_created_at_idx ON translation_cache (created_at);
@@ -0,0 +1,21 @@
+-- A dictionary loaded from a dump such as Wiktionary, one entry per word and
+-- part of speech
+CREATE TABLE dictionary_entries (
+    entry_id SERIAL PRIMARY KEY,
+    language VARCHAR(35) NOT NULL, -- BCP-47 tag
+    word VARCHAR(5000) NOT NULL,
+    part_of_speech VARCHAR(32) NOT NULL,
+    pronunciations TEXT[] NOT NULL DEFAULT '{}' -- In IPA
+);
+
+CREATE INDEX dictionary_entries_word_idx ON dictionary_entries (language, lower(word));
+
+CREATE TABLE dictionary_senses (
+    sense_id SERIAL PRIMARY KEY,
+    entry_id INT NOT NULL REFERENCES dictionary_entries(entry_id) ON DELETE CASCADE,
+    position INT NOT NULL, -- Order of the sense within its entry
+    definition TEXT NOT NULL,
+    examples TEXT[] NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX dictionary_senses_entry_id_idx ON dictionary_senses (entry_id, position);
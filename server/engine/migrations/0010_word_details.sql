@@ -0,0 +1,21 @@
+ALTER TABLE words
+    ADD COLUMN context VARCHAR(5000), -- The sentence the word was found in
+    ADD COLUMN part_of_speech VARCHAR(32),
+    ADD COLUMN senses JSONB NOT NULL DEFAULT '[]'; -- [{"definition": "...", "examples": ["..."]}]
+
+-- The definition of existing words becomes their only sense
+UPDATE words
+SET senses = jsonb_build_array(jsonb_build_object('definition', definition, 'examples', '[]'::jsonb))
+WHERE definition <> '';
+
+-- Senses, with their examples, and the context are searched along with the
+-- word and its definition
+ALTER TABLE words DROP COLUMN search_vector;
+ALTER TABLE words ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', word), 'A')
+    || setweight(to_tsvector('simple', definition), 'B')
+    || setweight(jsonb_to_tsvector('simple', senses, '["string"]'), 'B')
+    || setweight(to_tsvector('simple', coalesce(context, '')), 'C')
+) STORED;
+
+CREATE INDEX words_search_vector_idx ON words USING GIN (search_vector);
@@ -0,0 +1,26 @@
+ALTER TABLE words
+    ADD COLUMN lemma VARCHAR(5000), -- Normalized, and optionally lemmatized, word that duplicates are detected by
+    ADD COLUMN urls TEXT[] NOT NULL DEFAULT '{}'; -- Every page the word was saved from, oldest first
+
+UPDATE words SET urls = ARRAY[url] WHERE url <> '';
+
+-- Existing words are only normalized, and words that now collide with an
+-- earlier word are kept without a lemma
+UPDATE words
+SET lemma = normalized.lemma
+FROM (
+    SELECT word_id, lemma,
+        row_number() OVER (PARTITION BY user_id, lemma ORDER BY word_id) AS position
+    FROM (
+        SELECT word_id, user_id,
+            lower(btrim(regexp_replace(normalize(word, NFC), '\s+', ' ', 'g'))) AS lemma
+        FROM words
+    ) lemmas
+) normalized
+WHERE words.word_id = normalized.word_id AND normalized.position = 1;
+
+ALTER TABLE words DROP CONSTRAINT IF EXISTS words_user_id_word_key;
+CREATE UNIQUE INDEX words_user_id_lemma_key ON words (user_id, lemma);
+
+ALTER TABLE user_settings
+    ADD COLUMN lemmatize BOOLEAN NOT NULL DEFAULT FALSE; -- Whether English inflections count as the same word
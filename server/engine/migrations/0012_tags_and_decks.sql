@@ -0,0 +1,42 @@
+CREATE TABLE decks (
+    deck_id SERIAL PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL, -- JWT 'username' field
+    name VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX decks_user_id_name_key ON decks (user_id, lower(name));
+
+CREATE TABLE tags (
+    tag_id SERIAL PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL, -- JWT 'username' field
+    name VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX tags_user_id_name_key ON tags (user_id, lower(name));
+
+CREATE TABLE word_tags (
+    word_id INT NOT NULL REFERENCES words(word_id) ON DELETE CASCADE,
+    tag_id INT NOT NULL REFERENCES tags(tag_id) ON DELETE CASCADE,
+    PRIMARY KEY (word_id, tag_id)
+);
+
+CREATE INDEX word_tags_tag_id_idx ON word_tags (tag_id);
+
+-- A word belongs to at most one deck, and is kept when its deck is deleted
+ALTER TABLE words ADD COLUMN deck_id INT REFERENCES decks(deck_id) ON DELETE SET NULL;
+
+CREATE INDEX words_deck_id_idx ON words (deck_id);
+
+CREATE VIEW word_tag_names AS
+SELECT
+    words.word_id,
+    COALESCE(
+        array_agg(tags.name ORDER BY lower(tags.name)) FILTER (WHERE tags.tag_id IS NOT NULL),
+        '{}'
+    )::TEXT[] AS tag_names
+FROM words
+LEFT JOIN word_tags ON word_tags.word_id = words.word_id
+LEFT JOIN tags ON tags.tag_id = word_tags.tag_id
+GROUP BY words.word_id;
@@ -0,0 +1,14 @@
+CREATE TABLE access_tokens (
+    token_id SERIAL PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL, -- JWT 'username' field
+    name VARCHAR(64) NOT NULL,
+    token_hash BYTEA NOT NULL UNIQUE, -- SHA-256 of the token, which is never stored
+    prefix VARCHAR(16) NOT NULL, -- The start of the token, to recognize it by
+    scopes TEXT[] NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    last_used_at TIMESTAMP,
+    revoked_at TIMESTAMP
+);
+
+CREATE INDEX access_tokens_user_id_idx ON access_tokens (user_id);
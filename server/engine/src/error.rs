@@ -10,6 +10,8 @@ pub enum Error {
     Migration(#[from] sqlx::migrate::MigrateError),
     #[error("Third-party error: {0}")]
     ThirdParty(String),
+    #[error("Translation error: {0}")]
+    Translation(#[from] TranslationError),
     #[error("Unexpected error: {0}")]
     Unexpected(String),
 }
@@ -35,3 +37,44 @@ impl From<reqwest::Error> for Error {
         Error::ThirdParty(e.to_string())
     }
 }
+
+/// Why a translation provider could not translate
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TranslationError {
+    #[error("Translation quota exceeded")]
+    QuotaExceeded,
+    #[error("Too many translation requests")]
+    RateLimited,
+    #[error("Translation provider rejected the API key")]
+    InvalidKey,
+    #[error("Unsupported language: {0}")]
+    UnsupportedLanguage(String),
+    #[error("Translation provider timed out")]
+    Timeout,
+    #[error("Translation provider unavailable: {0}")]
+    Unavailable(String),
+    #[error("Invalid response from translation provider: {0}")]
+    InvalidResponse(String),
+}
+
+impl TranslationError {
+    /// Whether the same request may succeed when retried
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited | Self::Timeout | Self::Unavailable(_)
+        )
+    }
+}
+
+impl From<reqwest::Error> for TranslationError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout
+        } else if e.is_decode() {
+            Self::InvalidResponse(e.to_string())
+        } else {
+            Self::Unavailable(e.to_string())
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use super::error::Error;
+use super::types::{WordSense, USER_ID_PATTERN};
+use async_stream::try_stream;
+use chrono::NaiveDateTime;
+use futures::{Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use validator::{ValidationError, ValidationErrors};
+
+/// Size the exported data is buffered to before it is sent, in bytes
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The deck exported words are put in when imported into Anki
+const ANKI_DECK: &str = "A Few Words";
+
+/// The format of an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    #[default]
+    Json,
+    Anki, // Notes in the plain text format Anki imports with File > Import
+}
+
+impl ExportFormat {
+    /// The MIME type of the exported file
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Anki => "text/tab-separated-values; charset=utf-8",
+        }
+    }
+
+    /// The name the exported file is saved as
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "a-few-words.csv",
+            ExportFormat::Json => "a-few-words.json",
+            ExportFormat::Anki => "a-few-words-anki.txt",
+        }
+    }
+}
+
+/// Represents a word with its review history, as exported
+#[derive(Debug, FromRow, Serialize)]
+pub struct ExportedWord {
+    pub word: String,
+    pub definition: String,
+    pub url: String,
+    pub context: Option<String>,
+    pub part_of_speech: Option<String>,
+    #[sqlx(json)]
+    pub senses: Vec<WordSense>,
+    pub urls: Vec<String>,
+    pub deck: Option<String>, // The name of the deck
+    pub tags: Vec<String>,
+    pub date_added: NaiveDateTime,
+    pub retention: f64,
+    pub next_review_date: Option<NaiveDateTime>,
+    #[sqlx(json)]
+    pub reviews: Vec<ExportedReview>, // Oldest first
+}
+
+/// Represents a review of an exported word
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedReview {
+    pub review_date: NaiveDateTime,
+    pub recall_score: Option<i32>, // Missing for the session created with the word
+    pub next_review_date: Option<NaiveDateTime>,
+}
+
+/// Exports the words of a user with their review history
+///
+/// The words are read from the database as the returned stream is polled, so
+/// a large collection is never held in memory at once. CSV and Anki exports
+/// have one row per word, with the number of reviews and the last review in
+/// CSV; JSON exports have every review.
+///
+/// # Arguments
+///
+/// * `user_id` - The ID of the user whose words are exported
+/// * `format` - The format of the export
+/// * `pool` - The database connection pool
+///
+/// # Returns
+///
+/// Returns a stream of the chunks of the exported file, or an `Error` if the user ID is invalid
+pub fn export_words(
+    user_id: String,
+    format: ExportFormat,
+    pool: PgPool,
+) -> Result<impl Stream<Item = Result<Vec<u8>, Error>> + Send + 'static, Error> {
+    if !USER_ID_PATTERN.is_match(&user_id) {
+        let mut errors = ValidationErrors::new();
+        errors.add("user_id", ValidationError::new("Invalid user ID"));
+        return Err(Error::Validation(errors));
+    }
+
+    Ok(try_stream! {
+        let mut encoder = Encoder::new(format)?;
+        let mut words = sqlx::query_as::<_, ExportedWord>(
+            r#"
+            SELECT word, definition, url, context, part_of_speech, senses, urls, decks.name AS deck,
+                tag_names AS tags, date_added, retention, next_review_date,
+                COALESCE(
+                    (
+                        SELECT json_agg(
+                            json_build_object(
+                                'review_date', review_date,
+                                'recall_score', recall_score,
+                                'next_review_date', review_sessions.next_review_date
+                            )
+                            ORDER BY review_date, session_id
+                        )
+                        FROM review_sessions
+                        WHERE review_sessions.word_id = words.word_id
+                    ),
+                    '[]'
+                ) AS reviews
+            FROM words
+            INNER JOIN word_retention USING (word_id)
+            INNER JOIN word_schedule USING (word_id)
+            INNER JOIN word_tag_names USING (word_id)
+            LEFT JOIN decks USING (deck_id)
+            WHERE words.user_id = $1
+            ORDER BY date_added, word_id
+            "#,
+        )
+        .bind(&user_id)
+        .fetch(&pool);
+
+        while let Some(word) = words.try_next().await? {
+            encoder.push(&word)?;
+            if encoder.buffer.len() >= CHUNK_SIZE {
+                yield std::mem::take(&mut encoder.buffer);
+            }
+        }
+        encoder.finish();
+        yield encoder.buffer;
+    })
+}
+
+/// Writes exported words into a buffer
+struct Encoder {
+    format: ExportFormat,
+    buffer: Vec<u8>,
+    count: usize,
+}
+
+impl Encoder {
+    fn new(format: ExportFormat) -> Result<Self, Error> {
+        let mut encoder = Self {
+            format,
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            count: 0,
+        };
+        match format {
+            ExportFormat::Csv => encoder.write_record(
+                b',',
+                &[
+                    "word",
+                    "definition",
+                    "url",
+                    "context",
+                    "part_of_speech",
+                    "deck",
+                    "tags",
+                    "date_added",
+                    "retention",
+                    "next_review_date",
+                    "review_count",
+                    "last_review_date",
+                    "last_recall_score",
+                ],
+            )?,
+            ExportFormat::Json => encoder.buffer.push(b'['),
+            // Headers of the Anki text format, see
+            // https://docs.ankiweb.net/importing/text-files.html#file-headers
+            ExportFormat::Anki => encoder.buffer.extend_from_slice(
+                b"#separator:tab\n#html:false\n#notetype:Basic\n#deck column:3\n#tags column:4\n",
+            ),
+        }
+        Ok(encoder)
+    }
+
+    fn push(&mut self, word: &ExportedWord) -> Result<(), Error> {
+        match self.format {
+            ExportFormat::Csv => {
+                let last_review = word.reviews.iter().rev().find(|r| r.recall_score.is_some());
+                self.write_record(
+                    b',',
+                    &[
+                        word.word.as_str(),
+                        &word.definition,
+                        &word.url,
+                        word.context.as_deref().unwrap_or_default(),
+                        word.part_of_speech.as_deref().unwrap_or_default(),
+                        word.deck.as_deref().unwrap_or_default(),
+                        &word.tags.join(";"),
+                        &word.date_added.to_string(),
+                        &format!("{:.4}", word.retention),
+                        &word
+                            .next_review_date
+                            .map(|d| d.to_string())
+                            .unwrap_or_default(),
+                        &word
+                            .reviews
+                            .iter()
+                            .filter(|r| r.recall_score.is_some())
+                            .count()
+                            .to_string(),
+                        &last_review
+                            .map(|r| r.review_date.to_string())
+                            .unwrap_or_default(),
+                        &last_review
+                            .and_then(|r| r.recall_score)
+                            .map(|s| s.to_string())
+                            .unwrap_or_default(),
+                    ],
+                )?;
+            }
+            ExportFormat::Json => {
+                self.buffer
+                    .extend_from_slice(if self.count == 0 { b"\n" } else { b",\n" });
+                serde_json::to_writer(&mut self.buffer, word)
+                    .map_err(|e| Error::Unexpected(e.to_string()))?;
+            }
+            ExportFormat::Anki => {
+                let back = match &word.context {
+                    Some(context) => format!("{}\n\n{}", word.definition, context),
+                    None => word.definition.clone(),
+                };
+                let deck = match &word.deck {
+                    Some(deck) => format!("{ANKI_DECK}::{deck}"),
+                    None => ANKI_DECK.to_string(),
+                };
+                self.write_record(
+                    b'\t',
+                    &[word.word.as_str(), &back, &deck, &anki_tags(&word.tags)],
+                )?;
+            }
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        if self.format == ExportFormat::Json {
+            self.buffer.extend_from_slice(b"\n]\n");
+        }
+    }
+
+    fn write_record(&mut self, delimiter: u8, record: &[&str]) -> Result<(), Error> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(&mut self.buffer);
+        writer
+            .write_record(record)
+            .and_then(|()| writer.flush().map_err(csv::Error::from))
+            .map_err(|e| Error::Unexpected(e.to_string()))
+    }
+}
+
+/// Tags as Anki separates them, which cannot contain spaces
+fn anki_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("_"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anki_tags() {
+        assert_eq!(
+            anki_tags(&["novel reading".to_string(), "work".to_string()]),
+            "novel_reading work"
+        );
+    }
+}
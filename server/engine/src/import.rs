@@ -0,0 +1,658 @@
+use super::api::{check_deck_belongs_to_user, get_user_settings, insert_or_merge_word};
+use super::dictionary::Dictionary;
+use super::error::Error;
+use super::lemma;
+use super::translate::Language;
+use super::types::{NewWord, USER_ID_PATTERN};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, PgPool, SqliteConnection};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read, Write};
+use tempfile::NamedTempFile;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+/// Maximum number of words in one import
+pub const MAX_IMPORT_ROWS: usize = 10_000;
+
+/// Maximum size of an imported file, in bytes
+pub const MAX_IMPORT_SIZE: usize = 32 * 1024 * 1024;
+
+/// Maximum size of the collection of an Anki package once decompressed, in bytes
+pub const MAX_DECOMPRESSED_SIZE: usize = 2 * MAX_IMPORT_SIZE;
+
+/// Columns of a CSV or TSV file without a header row, in order
+const DEFAULT_COLUMNS: [&str; 6] = [
+    "word",
+    "definition",
+    "url",
+    "context",
+    "part_of_speech",
+    "tags",
+];
+
+/// Collections in an Anki package, newest format first
+const ANKI_COLLECTIONS: [&str; 3] = [
+    "collection.anki21b",
+    "collection.anki21",
+    "collection.anki2",
+];
+
+static HTML_TAG_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)<[^>]*>|\[sound:[^\]]*\]").unwrap());
+
+/// The format of an imported file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    Tsv,
+    Anki,   // An .apkg package exported from Anki
+    Kindle, // The vocab.db of the Kindle Vocabulary Builder
+}
+
+impl ImportFormat {
+    /// Guesses the format of a file from its contents
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(b"PK\x03\x04") {
+            ImportFormat::Anki
+        } else if data.starts_with(b"SQLite format 3\0") {
+            ImportFormat::Kindle
+        } else if data
+            .split(|b| *b == b'\n')
+            .next()
+            .is_some_and(|line| line.contains(&b'\t'))
+        {
+            ImportFormat::Tsv
+        } else {
+            ImportFormat::Csv
+        }
+    }
+}
+
+/// What to do with words the user already has, or that appear twice in a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnDuplicate {
+    #[default]
+    Skip,
+    Merge, // As when adding the word again, see `api::insert_word`
+}
+
+/// Represents a word read from an imported file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportRow {
+    pub row: usize, // Position in the file, starting from 1
+    pub word: String,
+    pub definition: Option<String>,
+    pub url: Option<String>,
+    pub context: Option<String>,
+    pub part_of_speech: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Represents how words are imported
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    pub dry_run: bool, // Report what would be imported without changing anything
+    pub on_duplicate: OnDuplicate,
+    pub deck_id: Option<i32>, // The deck every imported word is added to
+}
+
+/// What happens to a row of an imported file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportAction {
+    Insert,
+    Merge,
+    Skip,
+    Invalid,
+}
+
+/// Represents the outcome of importing a row
+#[derive(Debug)]
+pub struct ImportedRow {
+    pub row: usize,
+    pub word: String,
+    pub action: ImportAction,
+    pub errors: Option<ValidationErrors>, // Why the row is invalid
+}
+
+/// Represents the outcome of an import
+#[derive(Debug)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub inserted: usize,
+    pub merged: usize,
+    pub skipped: usize,
+    pub invalid: usize,
+    pub rows: Vec<ImportedRow>,
+}
+
+fn invalid_file(message: &'static str) -> Error {
+    let mut errors = ValidationErrors::new();
+    errors.add("file", ValidationError::new(message));
+    Error::Validation(errors)
+}
+
+/// Reads the words of an imported file
+///
+/// # Arguments
+///
+/// * `data` - The contents of the file
+/// * `format` - The format of the file
+///
+/// # Returns
+///
+/// Returns the rows of the file, or a validation error on `file` if it cannot be read
+pub async fn parse_import(data: &[u8], format: ImportFormat) -> Result<Vec<ImportRow>, Error> {
+    if data.len() > MAX_IMPORT_SIZE {
+        return Err(invalid_file("File must be at most 32 MiB"));
+    }
+    let rows = match format {
+        ImportFormat::Csv => parse_delimited(data, b',')?,
+        ImportFormat::Tsv => parse_delimited(data, b'\t')?,
+        ImportFormat::Anki => parse_anki(data).await?,
+        ImportFormat::Kindle => parse_kindle(data).await?,
+    };
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(invalid_file("File must have at most 10000 words"));
+    }
+    Ok(rows)
+}
+
+/// Reads a CSV or TSV file
+///
+/// The columns are named by a header row if the first row has a `word`
+/// column, and are taken in the order of `DEFAULT_COLUMNS` otherwise. Tags
+/// are separated by commas or semicolons.
+fn parse_delimited(data: &[u8], delimiter: u8) -> Result<Vec<ImportRow>, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data);
+
+    let mut columns: Vec<String> = DEFAULT_COLUMNS.iter().map(|c| c.to_string()).collect();
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|_| invalid_file("File is not valid CSV or TSV"))?;
+        let cells: Vec<String> = record
+            .iter()
+            .map(|cell| cell.trim_start_matches('\u{feff}').trim().to_string())
+            .collect();
+        if index == 0 && cells.iter().any(|cell| cell.eq_ignore_ascii_case("word")) {
+            columns = cells.iter().map(|cell| cell.to_lowercase()).collect();
+            continue;
+        }
+        if cells.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+
+        let cell = |name: &str| {
+            columns
+                .iter()
+                .position(|column| column == name)
+                .and_then(|i| cells.get(i))
+                .filter(|cell| !cell.is_empty())
+                .cloned()
+        };
+        rows.push(ImportRow {
+            row: record.position().map_or(index as u64 + 1, |p| p.line()) as usize,
+            word: cell("word").unwrap_or_default(),
+            definition: cell("definition"),
+            url: cell("url"),
+            context: cell("context"),
+            part_of_speech: cell("part_of_speech"),
+            tags: cell("tags")
+                .map(|tags| {
+                    tags.split([',', ';'])
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        });
+    }
+    Ok(rows)
+}
+
+/// Opens a SQLite database held in memory, by way of a temporary file
+async fn open_sqlite(data: &[u8]) -> Result<(NamedTempFile, SqliteConnection), Error> {
+    let mut file = NamedTempFile::new().map_err(|e| Error::Unexpected(e.to_string()))?;
+    file.write_all(data)
+        .map_err(|e| Error::Unexpected(e.to_string()))?;
+    let connection = SqliteConnectOptions::new()
+        .filename(file.path())
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(|_| invalid_file("File is not a SQLite database"))?;
+    Ok((file, connection))
+}
+
+/// Reads a compressed part of an Anki package, up to `MAX_DECOMPRESSED_SIZE`
+///
+/// Only that much is ever decompressed, so that a small package cannot expand
+/// to use up the memory of the server.
+fn read_decompressed(reader: impl Read) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    reader
+        .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+        .read_to_end(&mut data)
+        .map_err(|_| invalid_file("Anki package is corrupt"))?;
+    if data.len() > MAX_DECOMPRESSED_SIZE {
+        return Err(invalid_file("Anki collection is too large"));
+    }
+    Ok(data)
+}
+
+/// Reads the notes of an Anki package
+///
+/// The first field of a note is the word and the second its definition, with
+/// any HTML and sound references removed. The tags of the note are kept.
+async fn parse_anki(data: &[u8]) -> Result<Vec<ImportRow>, Error> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|_| invalid_file("File is not an Anki package"))?;
+    let name = ANKI_COLLECTIONS
+        .into_iter()
+        .find(|name| archive.index_for_name(name).is_some())
+        .ok_or_else(|| invalid_file("Anki package has no collection"))?;
+    let mut collection = read_decompressed(
+        archive
+            .by_name(name)
+            .map_err(|_| invalid_file("Anki package has no collection"))?,
+    )?;
+    if name == "collection.anki21b" {
+        collection = read_decompressed(
+            zstd::Decoder::new(collection.as_slice())
+                .map_err(|_| invalid_file("Anki package is corrupt"))?,
+        )?;
+    }
+
+    let (_file, mut connection) = open_sqlite(&collection).await?;
+    let notes: Vec<(String, String)> = sqlx::query_as("SELECT flds, tags FROM notes ORDER BY id")
+        .fetch_all(&mut connection)
+        .await
+        .map_err(|_| invalid_file("Anki package has no notes"))?;
+
+    Ok(notes
+        .into_iter()
+        .enumerate()
+        .map(|(index, (fields, tags))| {
+            let mut fields = fields.split('\u{1f}').map(strip_html);
+            ImportRow {
+                row: index + 1,
+                word: fields.next().unwrap_or_default(),
+                definition: fields.next().filter(|field| !field.is_empty()),
+                tags: tags.split_whitespace().map(str::to_string).collect(),
+                ..Default::default()
+            }
+        })
+        .collect())
+}
+
+/// Reads the lookups of a Kindle Vocabulary Builder database
+///
+/// The stem of each looked up word is imported, with the sentence it was
+/// looked up in as its context.
+async fn parse_kindle(data: &[u8]) -> Result<Vec<ImportRow>, Error> {
+    let (_file, mut connection) = open_sqlite(data).await?;
+    let lookups: Vec<(Option<String>, Option<String>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT WORDS.word, WORDS.stem, LOOKUPS.usage
+        FROM LOOKUPS
+        INNER JOIN WORDS ON WORDS.id = LOOKUPS.word_key
+        ORDER BY LOOKUPS.timestamp, LOOKUPS.id
+        "#,
+    )
+    .fetch_all(&mut connection)
+    .await
+    .map_err(|_| invalid_file("File is not a Kindle vocabulary database"))?;
+
+    Ok(lookups
+        .into_iter()
+        .enumerate()
+        .map(|(index, (word, stem, usage))| ImportRow {
+            row: index + 1,
+            word: stem
+                .filter(|stem| !stem.trim().is_empty())
+                .or(word)
+                .unwrap_or_default(),
+            context: usage
+                .map(|usage| lemma::normalize_word(&usage))
+                .filter(|usage| !usage.is_empty()),
+            ..Default::default()
+        })
+        .collect())
+}
+
+/// Removes the HTML of an Anki field
+fn strip_html(field: &str) -> String {
+    let text = HTML_TAG_PATTERN.replace_all(field, " ");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    lemma::normalize_word(&text)
+}
+
+/// Imports words for a user
+///
+/// Every row is validated like a new word, and rows without a definition get
+/// the first definition of the word in the English dictionary. Rows whose
+/// lemma matches a word of the user, or an earlier row, are duplicates. All
+/// words are inserted in one transaction, so either the whole import succeeds
+/// or nothing is changed.
+///
+/// # Arguments
+///
+/// * `user_id` - The ID of the user who imports the words
+/// * `rows` - The rows read by `parse_import`
+/// * `options` - How to import the words
+/// * `dictionary` - The dictionary missing definitions are looked up in
+/// * `pool` - The database connection pool
+///
+/// # Returns
+///
+/// Returns an `ImportReport` of what was, or in a dry run would be, done with each row, or an `Error` if the operation fails
+pub async fn import_words(
+    user_id: &str,
+    rows: Vec<ImportRow>,
+    options: &ImportOptions,
+    dictionary: &dyn Dictionary,
+    pool: &PgPool,
+) -> Result<ImportReport, Error> {
+    if !USER_ID_PATTERN.is_match(user_id) {
+        let mut errors = ValidationErrors::new();
+        errors.add("user_id", ValidationError::new("Invalid user ID"));
+        return Err(Error::Validation(errors));
+    }
+
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(invalid_file("File must have at most 10000 words"));
+    }
+
+    if let Some(deck_id) = options.deck_id {
+        check_deck_belongs_to_user(deck_id, user_id, pool).await?;
+    }
+
+    let settings = get_user_settings(user_id, pool).await?;
+    let words: Vec<String> = rows.iter().map(|row| row.word.clone()).collect();
+    let lemmas =
+        lemma::word_lemmas(&words, settings.lemmatize, &mut *pool.acquire().await?).await?;
+    let existing: HashSet<String> = sqlx::query_scalar(
+        r#"
+        SELECT lemma
+        FROM words
+        WHERE user_id = $1 AND lemma = ANY($2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(&lemmas)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    // Missing definitions are looked up at once
+    let undefined: Vec<String> = rows
+        .iter()
+        .filter(|row| row.definition.is_none() && !row.word.trim().is_empty())
+        .map(|row| row.word.clone())
+        .collect();
+    let definitions = if undefined.is_empty() {
+        HashMap::new()
+    } else {
+        dictionary
+            .lookup_definitions(&undefined, &Language::english())
+            .await?
+    };
+
+    let mut seen = HashSet::new();
+    let mut report = ImportReport {
+        dry_run: options.dry_run,
+        inserted: 0,
+        merged: 0,
+        skipped: 0,
+        invalid: 0,
+        rows: Vec::with_capacity(rows.len()),
+    };
+    let mut new_words = Vec::new();
+    for (row, lemma) in rows.into_iter().zip(lemmas) {
+        let definition = row
+            .definition
+            .or_else(|| definitions.get(&row.word.trim().to_lowercase()).cloned());
+        let mut new_word = NewWord::new(
+            user_id.to_string(),
+            row.word.clone(),
+            definition.clone().unwrap_or_default(),
+            row.url.unwrap_or_default(),
+        )
+        .with_forgetting_rate(0.5)
+        .with_tags(row.tags);
+        new_word.context = row.context;
+        new_word.part_of_speech = row.part_of_speech;
+        new_word.deck_id = options.deck_id;
+
+        let mut errors = match new_word.validate() {
+            Ok(()) => ValidationErrors::new(),
+            Err(errors) => errors,
+        };
+        if definition.is_none() {
+            errors.add(
+                "definition",
+                ValidationError::new("No definition given or found in the dictionary"),
+            );
+        }
+
+        let action = if !errors.is_empty() {
+            report.invalid += 1;
+            ImportAction::Invalid
+        } else if existing.contains(&lemma) || !seen.insert(lemma) {
+            match options.on_duplicate {
+                OnDuplicate::Skip => {
+                    report.skipped += 1;
+                    ImportAction::Skip
+                }
+                OnDuplicate::Merge => {
+                    report.merged += 1;
+                    ImportAction::Merge
+                }
+            }
+        } else {
+            report.inserted += 1;
+            ImportAction::Insert
+        };
+        if matches!(action, ImportAction::Insert | ImportAction::Merge) {
+            new_words.push(new_word);
+        }
+        report.rows.push(ImportedRow {
+            row: row.row,
+            word: row.word,
+            action,
+            errors: Some(errors).filter(|errors| !errors.is_empty()),
+        });
+    }
+
+    if !options.dry_run && !new_words.is_empty() {
+        let mut transaction = pool.begin().await?;
+        for new_word in new_words {
+            insert_or_merge_word(new_word, settings.lemmatize, &mut transaction).await?;
+        }
+        transaction.commit().await?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Connection;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(ImportFormat::detect(b"PK\x03\x04..."), ImportFormat::Anki);
+        assert_eq!(
+            ImportFormat::detect(b"SQLite format 3\0..."),
+            ImportFormat::Kindle
+        );
+        assert_eq!(
+            ImportFormat::detect(b"word\tdefinition\n"),
+            ImportFormat::Tsv
+        );
+        assert_eq!(
+            ImportFormat::detect(b"word,definition\n"),
+            ImportFormat::Csv
+        );
+    }
+
+    #[test]
+    fn test_parse_delimited() {
+        let csv = "\u{feff}Definition,Word,Tags\n\
+            a greeting,hello,\"greetings, basics\"\n\
+            ,,\n\
+            ,\"good, bye\",\n";
+        let rows = parse_delimited(csv.as_bytes(), b',').unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ImportRow {
+                    row: 2,
+                    word: "hello".to_string(),
+                    definition: Some("a greeting".to_string()),
+                    tags: vec!["greetings".to_string(), "basics".to_string()],
+                    ..Default::default()
+                },
+                ImportRow {
+                    row: 4,
+                    word: "good, bye".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        // Without a header the columns are taken in order
+        let rows = parse_delimited(b"hello\ta greeting\thttps://example.com", b'\t').unwrap();
+        assert_eq!(rows[0].definition.as_deref(), Some("a greeting"));
+        assert_eq!(rows[0].url.as_deref(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(
+            strip_html("<b>caf&eacute;</b><br>coffee&nbsp;&amp; cake [sound:cafe.mp3]"),
+            "caf&eacute; coffee & cake"
+        );
+    }
+
+    /// Creates a SQLite database from statements and returns its contents
+    async fn create_sqlite(statements: &[&str]) -> Vec<u8> {
+        let file = NamedTempFile::new().unwrap();
+        let mut connection = SqliteConnectOptions::new()
+            .filename(file.path())
+            .connect()
+            .await
+            .unwrap();
+        for statement in statements {
+            sqlx::query(statement)
+                .execute(&mut connection)
+                .await
+                .unwrap();
+        }
+        connection.close().await.unwrap();
+        std::fs::read(file.path()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_kindle() {
+        let data = create_sqlite(&[
+            "CREATE TABLE WORDS (id TEXT PRIMARY KEY, word TEXT, stem TEXT, lang TEXT)",
+            "CREATE TABLE LOOKUPS (id TEXT PRIMARY KEY, word_key TEXT, book_key TEXT, usage TEXT, timestamp INTEGER)",
+            "INSERT INTO WORDS VALUES ('en:running', 'running', 'run', 'en'), ('en:ephemeral', 'ephemeral', NULL, 'en')",
+            "INSERT INTO LOOKUPS VALUES ('1', 'en:ephemeral', 'b', 'An  ephemeral joy.', 2), ('2', 'en:running', 'b', 'She kept running.', 1)",
+        ])
+        .await;
+        assert_eq!(ImportFormat::detect(&data), ImportFormat::Kindle);
+
+        let rows = parse_import(&data, ImportFormat::Kindle).await.unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ImportRow {
+                    row: 1,
+                    word: "run".to_string(),
+                    context: Some("She kept running.".to_string()),
+                    ..Default::default()
+                },
+                ImportRow {
+                    row: 2,
+                    word: "ephemeral".to_string(),
+                    context: Some("An ephemeral joy.".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_anki() {
+        let collection = create_sqlite(&[
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, flds TEXT, tags TEXT)",
+            "INSERT INTO notes VALUES (1, 'hello\x1f<div>a greeting</div>', ' basics greetings ')",
+        ])
+        .await;
+        let mut package = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        package
+            .start_file("collection.anki2", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        package.write_all(&collection).unwrap();
+        let data = package.finish().unwrap().into_inner();
+        assert_eq!(ImportFormat::detect(&data), ImportFormat::Anki);
+
+        let rows = parse_import(&data, ImportFormat::Anki).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].word, "hello");
+        assert_eq!(rows[0].definition.as_deref(), Some("a greeting"));
+        assert_eq!(rows[0].tags, vec!["basics", "greetings"]);
+
+        assert!(matches!(
+            parse_import(b"PK\x03\x04", ImportFormat::Anki).await,
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_parse_anki_too_large() {
+        let oversized = vec![0; MAX_DECOMPRESSED_SIZE + 1];
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, entry) in [
+            ("collection.anki2", oversized.clone()),
+            (
+                "collection.anki21b",
+                zstd::encode_all(oversized.as_slice(), 0).unwrap(),
+            ),
+        ] {
+            let mut package = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            package.start_file(name, options).unwrap();
+            package.write_all(&entry).unwrap();
+            let data = package.finish().unwrap().into_inner();
+
+            let Err(Error::Validation(errors)) = parse_import(&data, ImportFormat::Anki).await
+            else {
+                panic!("{name} was not rejected");
+            };
+            assert_eq!(
+                errors.field_errors()["file"][0].code,
+                "Anki collection is too large"
+            );
+        }
+    }
+}
@@ -2,7 +2,16 @@ use error::Error;
 use sqlx::PgPool;
 
 pub mod api;
+pub mod dictionary;
+pub mod export;
+pub mod import;
+pub mod lemma;
+pub mod retention;
+pub mod scheduler;
+pub mod stats;
+pub mod tokens;
 pub mod translate;
+pub mod translation_cache;
 pub mod types;
 
 pub mod error;
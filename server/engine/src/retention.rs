@@ -0,0 +1,83 @@
+use chrono::Duration;
+
+/// Shortest interval a review is assumed to measure, in days
+///
+/// Reviewing a word right after the previous review says little about how
+/// fast it is forgotten, so shorter intervals are rounded up to this.
+pub const MIN_OBSERVED_INTERVAL_DAYS: f64 = 1.0;
+
+/// Estimated probability that a word was recalled, given its recall score
+///
+/// # Arguments
+///
+/// * `recall_score` - How well the word was recalled, from 1 to 5
+pub fn recall_probability(recall_score: i32) -> f64 {
+    match recall_score {
+        i32::MIN..=1 => 0.2,
+        2 => 0.4,
+        3 => 0.7,
+        4 => 0.9,
+        _ => 0.97,
+    }
+}
+
+/// Fits the retention rate of a word to the outcome of a review
+///
+/// The forgetting curve of a word is modelled as `retention_rate ^ days`, where
+/// `retention_rate` is the fraction of the memory kept per day. Each review
+/// observes the rate that would explain the recall score after the elapsed
+/// time, and the new rate is the running average of the previous rate and the
+/// observations.
+///
+/// # Arguments
+///
+/// * `retention_rate` - The retention rate before the review, between 0 and 1
+/// * `review_count` - Number of reviews the current rate is based on
+/// * `recall_score` - How well the word was recalled, from 1 to 5
+/// * `elapsed` - Time since the previous review
+///
+/// # Returns
+///
+/// Returns the new retention rate, between 0 and 1
+pub fn next_retention_rate(
+    retention_rate: f64,
+    review_count: i32,
+    recall_score: i32,
+    elapsed: Duration,
+) -> f64 {
+    let elapsed_days = (elapsed.num_seconds() as f64 / 86400.0).max(MIN_OBSERVED_INTERVAL_DAYS);
+    let observed_rate = recall_probability(recall_score).powf(1.0 / elapsed_days);
+    // The previous rate counts as one observation more than the reviews it is
+    // based on, so the initial guess is weighted like a single review
+    let weight = review_count.max(0) as f64 + 1.0;
+    ((retention_rate * weight + observed_rate) / (weight + 1.0)).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_good_recall_raises_retention_rate() {
+        let rate = next_retention_rate(0.5, 0, 5, Duration::days(1));
+        assert!((rate - (0.5 + 0.97) / 2.0).abs() < 1e-9);
+
+        let later = next_retention_rate(rate, 1, 5, Duration::days(10));
+        assert!(later > rate);
+    }
+
+    #[test]
+    fn test_poor_recall_lowers_retention_rate() {
+        let rate = next_retention_rate(0.9, 3, 1, Duration::days(1));
+        assert!(rate < 0.9);
+        assert!(rate > 0.0);
+    }
+
+    #[test]
+    fn test_immediate_review_counts_as_one_day() {
+        assert_eq!(
+            next_retention_rate(0.5, 0, 4, Duration::zero()),
+            next_retention_rate(0.5, 0, 4, Duration::days(1))
+        );
+    }
+}
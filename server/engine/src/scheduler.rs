@@ -0,0 +1,370 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+/// Ease factor a word starts with under SM-2
+pub const INITIAL_EASE_FACTOR: f64 = 2.5;
+
+/// Lowest ease factor SM-2 allows
+pub const MIN_EASE_FACTOR: f64 = 1.3;
+
+/// Retention probability FSRS schedules the next review for
+pub const DESIRED_RETENTION: f64 = 0.9;
+
+/// Default FSRS model weights
+const FSRS_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+/// Exponent of the FSRS power forgetting curve
+const FSRS_DECAY: f64 = -0.5;
+
+/// Chosen so that retrievability is 90% when the elapsed time equals the stability
+const FSRS_FACTOR: f64 = 19.0 / 81.0;
+
+/// Scheduling state of a word, carried from one review to the next
+///
+/// The state holds the memory model of every scheduler, so a user can switch
+/// schedulers without losing the progress of their words.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchedulingState {
+    /// Number of consecutive successful reviews
+    pub repetitions: i32,
+    /// SM-2 ease factor
+    pub ease_factor: f64,
+    /// FSRS stability in days, zero for a word that has not been reviewed yet
+    pub stability: f64,
+    /// FSRS difficulty between 1 and 10, zero for a word that has not been reviewed yet
+    pub difficulty: f64,
+    /// Time until the next review
+    pub interval: Duration,
+}
+
+impl Default for SchedulingState {
+    fn default() -> Self {
+        Self {
+            repetitions: 0,
+            ease_factor: INITIAL_EASE_FACTOR,
+            stability: 0.0,
+            difficulty: 0.0,
+            interval: Duration::zero(),
+        }
+    }
+}
+
+/// A spaced-repetition algorithm
+pub trait Scheduler {
+    /// Computes the state of a word after a review
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state of the word before the review
+    /// * `recall_score` - How well the word was recalled, from 1 to 5
+    /// * `elapsed` - Time since the previous review
+    ///
+    /// # Returns
+    ///
+    /// Returns the new `SchedulingState`, whose `interval` is the time until the next review
+    fn schedule(
+        &self,
+        state: &SchedulingState,
+        recall_score: i32,
+        elapsed: Duration,
+    ) -> SchedulingState;
+}
+
+/// The SuperMemo 2 algorithm
+///
+/// Recall scores below 3 reset the repetitions, higher scores grow the
+/// interval by the ease factor, which itself adapts to each score.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sm2;
+
+impl Scheduler for Sm2 {
+    fn schedule(
+        &self,
+        state: &SchedulingState,
+        recall_score: i32,
+        _elapsed: Duration,
+    ) -> SchedulingState {
+        let quality = recall_score.clamp(0, 5) as f64;
+        let ease_factor = (state.ease_factor
+            + (0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02)))
+            .max(MIN_EASE_FACTOR);
+
+        let (repetitions, interval_days) = if recall_score < 3 {
+            (0, 1)
+        } else {
+            let interval_days = match state.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (state.interval.num_days().max(1) as f64 * state.ease_factor).round() as i64,
+            };
+            (state.repetitions + 1, interval_days)
+        };
+
+        SchedulingState {
+            repetitions,
+            ease_factor,
+            interval: Duration::days(interval_days),
+            ..*state
+        }
+    }
+}
+
+/// A simplified Free Spaced Repetition Scheduler (FSRS v4.5)
+///
+/// Each word is modelled by its stability, the number of days until recall
+/// probability drops to 90%, and its difficulty. The next review is scheduled
+/// when the predicted recall probability reaches `DESIRED_RETENTION`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Fsrs;
+
+impl Fsrs {
+    /// Maps a recall score from 1 to 5 onto the FSRS grades Again, Hard, Good and Easy
+    fn grade(recall_score: i32) -> f64 {
+        match recall_score {
+            i32::MIN..=2 => 1.0,
+            3 => 2.0,
+            4 => 3.0,
+            _ => 4.0,
+        }
+    }
+
+    fn initial_stability(grade: f64) -> f64 {
+        FSRS_WEIGHTS[grade as usize - 1]
+    }
+
+    fn initial_difficulty(grade: f64) -> f64 {
+        (FSRS_WEIGHTS[4] - (grade - 3.0) * FSRS_WEIGHTS[5]).clamp(1.0, 10.0)
+    }
+
+    /// Predicted probability of recalling a word `elapsed_days` after its last review
+    pub fn retrievability(stability: f64, elapsed_days: f64) -> f64 {
+        if stability <= 0.0 {
+            return 0.0;
+        }
+        (1.0 + FSRS_FACTOR * elapsed_days.max(0.0) / stability).powf(FSRS_DECAY)
+    }
+
+    fn next_difficulty(difficulty: f64, grade: f64) -> f64 {
+        let difficulty = difficulty - FSRS_WEIGHTS[6] * (grade - 3.0);
+        let difficulty =
+            FSRS_WEIGHTS[7] * Self::initial_difficulty(3.0) + (1.0 - FSRS_WEIGHTS[7]) * difficulty;
+        difficulty.clamp(1.0, 10.0)
+    }
+
+    fn next_recall_stability(
+        stability: f64,
+        difficulty: f64,
+        retrievability: f64,
+        grade: f64,
+    ) -> f64 {
+        let hard_penalty = if grade == 2.0 { FSRS_WEIGHTS[15] } else { 1.0 };
+        let easy_bonus = if grade == 4.0 { FSRS_WEIGHTS[16] } else { 1.0 };
+        stability
+            * (FSRS_WEIGHTS[8].exp()
+                * (11.0 - difficulty)
+                * stability.powf(-FSRS_WEIGHTS[9])
+                * ((FSRS_WEIGHTS[10] * (1.0 - retrievability)).exp() - 1.0)
+                * hard_penalty
+                * easy_bonus
+                + 1.0)
+    }
+
+    fn next_forget_stability(stability: f64, difficulty: f64, retrievability: f64) -> f64 {
+        FSRS_WEIGHTS[11]
+            * difficulty.powf(-FSRS_WEIGHTS[12])
+            * ((stability + 1.0).powf(FSRS_WEIGHTS[13]) - 1.0)
+            * (FSRS_WEIGHTS[14] * (1.0 - retrievability)).exp()
+    }
+
+    fn interval_days(stability: f64) -> i64 {
+        let days = stability / FSRS_FACTOR * (DESIRED_RETENTION.powf(1.0 / FSRS_DECAY) - 1.0);
+        (days.round() as i64).max(1)
+    }
+}
+
+impl Scheduler for Fsrs {
+    fn schedule(
+        &self,
+        state: &SchedulingState,
+        recall_score: i32,
+        elapsed: Duration,
+    ) -> SchedulingState {
+        let grade = Self::grade(recall_score);
+
+        let (stability, difficulty) = if state.stability <= 0.0 {
+            (
+                Self::initial_stability(grade),
+                Self::initial_difficulty(grade),
+            )
+        } else {
+            let elapsed_days = elapsed.num_seconds() as f64 / 86400.0;
+            let retrievability = Self::retrievability(state.stability, elapsed_days);
+            let stability = if grade == 1.0 {
+                Self::next_forget_stability(state.stability, state.difficulty, retrievability)
+            } else {
+                Self::next_recall_stability(
+                    state.stability,
+                    state.difficulty,
+                    retrievability,
+                    grade,
+                )
+            };
+            (stability, Self::next_difficulty(state.difficulty, grade))
+        };
+
+        let repetitions = if grade == 1.0 {
+            0
+        } else {
+            state.repetitions + 1
+        };
+
+        SchedulingState {
+            repetitions,
+            stability,
+            difficulty,
+            interval: Duration::days(Self::interval_days(stability)),
+            ..*state
+        }
+    }
+}
+
+/// The scheduler a user has chosen
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulerKind {
+    #[default]
+    Sm2,
+    Fsrs,
+}
+
+impl SchedulerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sm2 => "sm2",
+            Self::Fsrs => "fsrs",
+        }
+    }
+
+    pub fn scheduler(&self) -> &'static dyn Scheduler {
+        match self {
+            Self::Sm2 => &Sm2,
+            Self::Fsrs => &Fsrs,
+        }
+    }
+}
+
+impl fmt::Display for SchedulerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SchedulerKind {
+    type Err = UnknownSchedulerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sm2" => Ok(Self::Sm2),
+            "fsrs" => Ok(Self::Fsrs),
+            _ => Err(UnknownSchedulerError(s.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for SchedulerKind {
+    type Error = UnknownSchedulerError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Unknown scheduler: {0}")]
+pub struct UnknownSchedulerError(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review_sequence(scheduler: &dyn Scheduler, recall_scores: &[i32]) -> Vec<SchedulingState> {
+        let mut state = SchedulingState::default();
+        recall_scores
+            .iter()
+            .map(|&recall_score| {
+                state = scheduler.schedule(&state, recall_score, state.interval);
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sm2_intervals_grow() {
+        let states = review_sequence(&Sm2, &[4, 4, 4, 4]);
+        let intervals: Vec<i64> = states.iter().map(|s| s.interval.num_days()).collect();
+        assert_eq!(intervals, vec![1, 6, 15, 38]);
+        assert_eq!(states[3].repetitions, 4);
+        assert_eq!(states[3].ease_factor, INITIAL_EASE_FACTOR);
+    }
+
+    #[test]
+    fn test_sm2_failure_resets_repetitions() {
+        let states = review_sequence(&Sm2, &[5, 5, 1]);
+        assert_eq!(states[1].interval.num_days(), 6);
+        assert_eq!(states[2].repetitions, 0);
+        assert_eq!(states[2].interval.num_days(), 1);
+        assert!(states[2].ease_factor < states[1].ease_factor);
+    }
+
+    #[test]
+    fn test_sm2_ease_factor_floor() {
+        let states = review_sequence(&Sm2, &[1; 10]);
+        assert_eq!(states[9].ease_factor, MIN_EASE_FACTOR);
+    }
+
+    #[test]
+    fn test_fsrs_first_review() {
+        let state = Fsrs.schedule(&SchedulingState::default(), 4, Duration::zero());
+        assert_eq!(state.stability, FSRS_WEIGHTS[2]);
+        assert_eq!(state.difficulty, FSRS_WEIGHTS[4]);
+        assert_eq!(state.interval.num_days(), 4);
+        assert_eq!(state.repetitions, 1);
+    }
+
+    #[test]
+    fn test_fsrs_successful_reviews_increase_stability() {
+        let states = review_sequence(&Fsrs, &[4, 4, 4]);
+        assert!(states[1].stability > states[0].stability);
+        assert!(states[2].stability > states[1].stability);
+        assert!(states[2].interval > states[1].interval);
+    }
+
+    #[test]
+    fn test_fsrs_lapse_decreases_stability() {
+        let states = review_sequence(&Fsrs, &[4, 4, 1]);
+        assert!(states[2].stability < states[1].stability);
+        assert!(states[2].difficulty > states[1].difficulty);
+        assert_eq!(states[2].repetitions, 0);
+    }
+
+    #[test]
+    fn test_fsrs_retrievability() {
+        assert_eq!(Fsrs::retrievability(10.0, 0.0), 1.0);
+        assert!((Fsrs::retrievability(10.0, 10.0) - DESIRED_RETENTION).abs() < 1e-9);
+        assert_eq!(Fsrs::retrievability(0.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_scheduler_kind_round_trip() {
+        for kind in [SchedulerKind::Sm2, SchedulerKind::Fsrs] {
+            assert_eq!(kind.as_str().parse::<SchedulerKind>().unwrap(), kind);
+        }
+        assert!("anki".parse::<SchedulerKind>().is_err());
+    }
+}
@@ -1,64 +1,779 @@
-use reqwest::Client;
+use super::error::{Error, TranslationError};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use super::error::Error;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub enum Language {
-    Chinese,
-    English,
-}
+/// Maximum length of a language tag
+pub const MAX_LANGUAGE_TAG_LENGTH: usize = 35;
+
+/// Number of times a transient failure of a provider is retried by default
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+
+/// Wait before the first retry by default
+pub const DEFAULT_BACKOFF: Duration = Duration::from_millis(200);
+
+/// How long a single request to a provider may take by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Language, optional script, optional region and variants of a BCP-47 tag
+static LANGUAGE_TAG_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^[a-zA-Z]{2,3}(-[a-zA-Z]{4})?(-(?:[a-zA-Z]{2}|[0-9]{3}))?(-(?:[a-zA-Z0-9]{5,8}|[0-9][a-zA-Z0-9]{3}))*$",
+    )
+    .unwrap()
+});
+
+/// A BCP-47 language tag, such as `en`, `pt-BR` or `zh-Hant-TW`
+///
+/// Tags are kept in their canonical case, so equal languages compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Language(String);
 
 impl Language {
-    fn to_str(&self) -> &str {
-        match self {
-            Self::Chinese => "zh",
-            Self::English => "en",
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// English, the language words are looked up in by default
+    pub fn english() -> Self {
+        Self("en".to_string())
+    }
+
+    /// The primary language subtag, such as `zh` for `zh-Hant-TW`
+    pub fn primary(&self) -> &str {
+        self.0.split('-').next().unwrap_or(&self.0)
+    }
+}
+
+/// Chinese, the language words were translated into before users could choose
+impl Default for Language {
+    fn default() -> Self {
+        Self("zh".to_string())
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Language {
+    type Err = InvalidLanguageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.len() > MAX_LANGUAGE_TAG_LENGTH || !LANGUAGE_TAG_PATTERN.is_match(s) {
+            return Err(InvalidLanguageError(s.to_string()));
+        }
+        let tag = s
+            .split('-')
+            .enumerate()
+            .map(|(i, subtag)| match subtag.len() {
+                _ if i == 0 => subtag.to_lowercase(),
+                2 => subtag.to_uppercase(),
+                4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                    subtag[..1].to_uppercase() + &subtag[1..].to_lowercase()
+                }
+                _ => subtag.to_lowercase(),
+            })
+            .collect::<Vec<_>>()
+            .join("-");
+        Ok(Self(tag))
+    }
+}
+
+impl TryFrom<String> for Language {
+    type Error = InvalidLanguageError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Language> for String {
+    fn from(language: Language) -> Self {
+        language.0
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid language tag: {0}")]
+pub struct InvalidLanguageError(String);
+
+/// The language to translate from, `auto` lets the provider detect it
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SourceLanguage {
+    #[default]
+    Auto,
+    Language(Language),
+}
+
+impl FromStr for SourceLanguage {
+    type Err = InvalidLanguageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("auto") {
+            Ok(Self::Auto)
+        } else {
+            s.parse().map(Self::Language)
+        }
+    }
+}
+
+impl TryFrom<String> for SourceLanguage {
+    type Error = InvalidLanguageError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Represents translated text
+#[derive(Debug)]
+pub struct Translation {
+    pub text: String,
+    pub source: Option<Language>, // The given or detected source language, if known
+}
+
+/// A machine translation provider
+#[async_trait]
+pub trait Translator: Send + Sync {
+    /// Short name of the provider, such as `google`
+    fn provider(&self) -> &'static str;
+
+    /// Translates text into the target language
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to translate
+    /// * `source` - The language of the text, or `Auto` to detect it
+    /// * `target` - The language to translate into
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Translation` if successful, or an `Error` if the operation fails
+    async fn translate(
+        &self,
+        text: &str,
+        source: &SourceLanguage,
+        target: &Language,
+    ) -> Result<Translation, Error>;
+}
+
+/// The given source language, or the one the provider detected
+fn source_language(source: &SourceLanguage, detected: Option<&str>) -> Option<Language> {
+    match source {
+        SourceLanguage::Language(source) => Some(source.clone()),
+        SourceLanguage::Auto => detected.and_then(|code| code.parse().ok()),
+    }
+}
+
+/// Sends a request to a provider and decodes its successful response
+///
+/// Unsuccessful responses are turned into errors by `error`, from their status
+/// and body.
+async fn send<T: DeserializeOwned>(
+    request: RequestBuilder,
+    error: fn(StatusCode, &str) -> TranslationError,
+) -> Result<T, TranslationError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(error(status, &body));
+    }
+    Ok(response.json::<T>().await?)
+}
+
+/// The error of a response no provider-specific rule applies to
+fn status_error(status: StatusCode, message: &str) -> TranslationError {
+    match status.as_u16() {
+        401 | 403 => TranslationError::InvalidKey,
+        429 => TranslationError::RateLimited,
+        408 | 504 => TranslationError::Timeout,
+        500..=599 => TranslationError::Unavailable(format!("{status}: {message}")),
+        _ => TranslationError::InvalidResponse(format!("{status}: {message}")),
+    }
+}
+
+/// Google Cloud Translation, basic edition
+pub struct GoogleTranslator {
+    client: Client,
+    api_key: String,
+}
+
+impl GoogleTranslator {
+    pub fn new(client: Client, api_key: String) -> Self {
+        Self { client, api_key }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleResponse {
+    data: GoogleData,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleData {
+    translations: Vec<GoogleTranslation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleTranslation {
+    translated_text: String,
+    detected_source_language: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GoogleErrorResponse {
+    error: GoogleErrorBody,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct GoogleErrorBody {
+    message: String,
+    errors: Vec<GoogleErrorDetail>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct GoogleErrorDetail {
+    reason: String,
+}
+
+fn google_error(status: StatusCode, body: &str) -> TranslationError {
+    let error = serde_json::from_str::<GoogleErrorResponse>(body)
+        .unwrap_or_default()
+        .error;
+    let reasons = error.errors.iter().map(|e| e.reason.as_str());
+    for reason in reasons {
+        match reason {
+            "keyInvalid" | "keyExpired" => return TranslationError::InvalidKey,
+            "dailyLimitExceeded" | "quotaExceeded" => return TranslationError::QuotaExceeded,
+            "rateLimitExceeded" | "userRateLimitExceeded" => return TranslationError::RateLimited,
+            _ => {}
+        }
+    }
+    if status == StatusCode::BAD_REQUEST && error.message.contains("Invalid Value") {
+        return TranslationError::UnsupportedLanguage(error.message);
+    }
+    status_error(status, &error.message)
+}
+
+#[async_trait]
+impl Translator for GoogleTranslator {
+    fn provider(&self) -> &'static str {
+        "google"
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source: &SourceLanguage,
+        target: &Language,
+    ) -> Result<Translation, Error> {
+        let mut request_body = json!({
+            "q": text,
+            "target": target.as_str(),
+            "format": "text"
+        });
+        if let SourceLanguage::Language(source) = source {
+            request_body["source"] = json!(source.as_str());
+        }
+
+        let request = self
+            .client
+            .post("https://translation.googleapis.com/language/translate/v2")
+            .query(&[("key", &self.api_key)])
+            .json(&request_body);
+        let response: GoogleResponse = send(request, google_error).await?;
+
+        let translation = response
+            .data
+            .translations
+            .first()
+            .ok_or_else(|| TranslationError::InvalidResponse("No translations".to_string()))?;
+        Ok(Translation {
+            text: translation.translated_text.clone(),
+            source: source_language(source, translation.detected_source_language.as_deref()),
+        })
+    }
+}
+
+/// DeepL, on the free or the pro API depending on the key
+pub struct DeepLTranslator {
+    client: Client,
+    api_key: String,
+}
+
+impl DeepLTranslator {
+    pub fn new(client: Client, api_key: String) -> Self {
+        Self { client, api_key }
+    }
+
+    /// Keys of the free plan end with `:fx` and only work on the free API
+    fn base_url(&self) -> &'static str {
+        if self.api_key.ends_with(":fx") {
+            "https://api-free.deepl.com"
+        } else {
+            "https://api.deepl.com"
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TranslationResponse {
-    data: TranslationData,
+#[derive(Debug, Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepLTranslation {
+    text: String,
+    detected_source_language: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct DeepLErrorResponse {
+    message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TranslationData {
-    translations: Vec<TranslatedText>,
+fn deepl_error(status: StatusCode, body: &str) -> TranslationError {
+    let message = serde_json::from_str::<DeepLErrorResponse>(body)
+        .unwrap_or_default()
+        .message;
+    match status.as_u16() {
+        456 => TranslationError::QuotaExceeded,
+        529 => TranslationError::RateLimited,
+        400 if message.contains("not supported") => TranslationError::UnsupportedLanguage(message),
+        _ => status_error(status, &message),
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TranslatedText {
-    #[serde(rename = "translatedText")]
+#[async_trait]
+impl Translator for DeepLTranslator {
+    fn provider(&self) -> &'static str {
+        "deepl"
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source: &SourceLanguage,
+        target: &Language,
+    ) -> Result<Translation, Error> {
+        // DeepL takes upper-case codes, source languages without a region
+        let mut request_body = json!({
+            "text": [text],
+            "target_lang": target.as_str().to_uppercase(),
+        });
+        if let SourceLanguage::Language(source) = source {
+            request_body["source_lang"] = json!(source.primary().to_uppercase());
+        }
+
+        let request = self
+            .client
+            .post(format!("{}/v2/translate", self.base_url()))
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .json(&request_body);
+        let response: DeepLResponse = send(request, deepl_error).await?;
+
+        let translation = response
+            .translations
+            .first()
+            .ok_or_else(|| TranslationError::InvalidResponse("No translations".to_string()))?;
+        Ok(Translation {
+            text: translation.text.clone(),
+            source: source_language(source, translation.detected_source_language.as_deref()),
+        })
+    }
+}
+
+/// LibreTranslate, hosted or self-hosted
+pub struct LibreTranslator {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>, // Only needed by instances that require one
+}
+
+impl LibreTranslator {
+    pub fn new(client: Client, base_url: String, api_key: Option<String>) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibreResponse {
     translated_text: String,
+    detected_language: Option<LibreDetectedLanguage>,
 }
 
-pub async fn translate_text(
-    api_key: &str,
-    text: &str,
-    source_lang: Language,
-    target_lang: Language,
-) -> Result<String, Error> {
-    let client = Client::new();
-    let url = format!(
-        "https://translation.googleapis.com/language/translate/v2?key={}",
-        api_key
-    );
-
-    let request_body = json!({
-        "q": text,
-        "source": source_lang.to_str(),
-        "target": target_lang.to_str(),
-        "format": "text"
-    });
-
-    let response = client
-        .post(&url)
-        .json(&request_body)
-        .send()
-        .await?
-        .json::<TranslationResponse>()
-        .await?;
-
-    Ok(response.data.translations[0].translated_text.clone())
+#[derive(Debug, Deserialize)]
+struct LibreDetectedLanguage {
+    language: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LibreErrorResponse {
+    error: String,
+}
+
+fn libre_error(status: StatusCode, body: &str) -> TranslationError {
+    let message = serde_json::from_str::<LibreErrorResponse>(body)
+        .unwrap_or_default()
+        .error;
+    match status.as_u16() {
+        400 if message.contains("not supported") => TranslationError::UnsupportedLanguage(message),
+        _ => status_error(status, &message),
+    }
+}
+
+#[async_trait]
+impl Translator for LibreTranslator {
+    fn provider(&self) -> &'static str {
+        "libretranslate"
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source: &SourceLanguage,
+        target: &Language,
+    ) -> Result<Translation, Error> {
+        let request_body = json!({
+            "q": text,
+            "source": match source {
+                SourceLanguage::Auto => "auto",
+                SourceLanguage::Language(source) => source.as_str(),
+            },
+            "target": target.as_str(),
+            "format": "text",
+            "api_key": self.api_key,
+        });
+
+        let request = self
+            .client
+            .post(format!("{}/translate", self.base_url))
+            .json(&request_body);
+        let response: LibreResponse = send(request, libre_error).await?;
+
+        Ok(Translation {
+            source: source_language(
+                source,
+                response
+                    .detected_language
+                    .as_ref()
+                    .map(|detected| detected.language.as_str()),
+            ),
+            text: response.translated_text,
+        })
+    }
+}
+
+/// Retries a provider when it fails transiently
+///
+/// Each attempt is cut off after the timeout, and the wait before the next
+/// attempt doubles every time. Errors that would only repeat, such as an
+/// invalid key, are returned right away.
+pub struct RetryingTranslator {
+    inner: Arc<dyn Translator>,
+    max_retries: u32,
+    backoff: Duration,
+    timeout: Duration,
+}
+
+impl RetryingTranslator {
+    pub fn new(inner: Arc<dyn Translator>) -> Self {
+        Self {
+            inner,
+            max_retries: DEFAULT_MAX_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the wait before the first retry
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets how long a single attempt may take
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait]
+impl Translator for RetryingTranslator {
+    fn provider(&self) -> &'static str {
+        self.inner.provider()
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source: &SourceLanguage,
+        target: &Language,
+    ) -> Result<Translation, Error> {
+        let mut attempt = 0;
+        loop {
+            let error = match tokio::time::timeout(
+                self.timeout,
+                self.inner.translate(text, source, target),
+            )
+            .await
+            {
+                Ok(Err(Error::Translation(error))) if error.is_transient() => error,
+                Ok(result) => return result,
+                Err(_) => TranslationError::Timeout,
+            };
+            if attempt >= self.max_retries {
+                return Err(error.into());
+            }
+            tokio::time::sleep(self.backoff * 2u32.pow(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Translates from a fixed table, for tests and local development
+///
+/// Text without a translation in the table is returned unchanged.
+#[derive(Default)]
+pub struct FakeTranslator {
+    translations: HashMap<(String, Language), Translation>,
+}
+
+impl FakeTranslator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_translation(
+        mut self,
+        text: &str,
+        source: Language,
+        target: Language,
+        translation: &str,
+    ) -> Self {
+        self.translations.insert(
+            (text.to_string(), target),
+            Translation {
+                text: translation.to_string(),
+                source: Some(source),
+            },
+        );
+        self
+    }
+}
+
+#[async_trait]
+impl Translator for FakeTranslator {
+    fn provider(&self) -> &'static str {
+        "fake"
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source: &SourceLanguage,
+        target: &Language,
+    ) -> Result<Translation, Error> {
+        let translation = self.translations.get(&(text.to_string(), target.clone()));
+        Ok(Translation {
+            text: translation.map_or(text, |t| t.text.as_str()).to_string(),
+            source: source_language(
+                source,
+                translation.and_then(|t| t.source.as_ref().map(Language::as_str)),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_language_tags_are_canonicalized() {
+        let tag = |s: &str| s.parse::<Language>().map(|l| l.to_string()).ok();
+        assert_eq!(tag("EN"), Some("en".to_string()));
+        assert_eq!(tag("pt-br"), Some("pt-BR".to_string()));
+        assert_eq!(tag("zh-hant-tw"), Some("zh-Hant-TW".to_string()));
+        assert_eq!(tag("es-419"), Some("es-419".to_string()));
+        assert_eq!(tag(" de "), Some("de".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_language_tags_are_rejected() {
+        for s in ["", "e", "english", "en_US", "en-", "zh-Hant-TW-x", "auto"] {
+            assert!(s.parse::<Language>().is_err(), "{s} should be invalid");
+        }
+        assert_eq!("zh-Hant-TW".parse::<Language>().unwrap().primary(), "zh");
+    }
+
+    #[test]
+    fn test_source_language_auto() {
+        assert_eq!(
+            "auto".parse::<SourceLanguage>().unwrap(),
+            SourceLanguage::Auto
+        );
+        assert_eq!(
+            "fr".parse::<SourceLanguage>().unwrap(),
+            SourceLanguage::Language("fr".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fake_translator() {
+        let english: Language = "en".parse().unwrap();
+        let chinese: Language = "zh".parse().unwrap();
+        let translator = FakeTranslator::new().with_translation(
+            "hello",
+            english.clone(),
+            chinese.clone(),
+            "你好",
+        );
+
+        let translation = translator
+            .translate("hello", &SourceLanguage::Auto, &chinese)
+            .await
+            .unwrap();
+        assert_eq!(translation.text, "你好");
+        assert_eq!(translation.source, Some(english));
+
+        let translation = translator
+            .translate("world", &SourceLanguage::Auto, &chinese)
+            .await
+            .unwrap();
+        assert_eq!(translation.text, "world");
+        assert_eq!(translation.source, None);
+    }
+
+    #[test]
+    fn test_provider_errors() {
+        let google_body = r#"{"error": {"code": 400, "message": "API key not valid.", "errors": [{"reason": "keyInvalid"}]}}"#;
+        assert_eq!(
+            google_error(StatusCode::BAD_REQUEST, google_body),
+            TranslationError::InvalidKey
+        );
+        let google_body = r#"{"error": {"code": 400, "message": "Invalid Value", "errors": [{"reason": "invalid"}]}}"#;
+        assert_eq!(
+            google_error(StatusCode::BAD_REQUEST, google_body),
+            TranslationError::UnsupportedLanguage("Invalid Value".to_string())
+        );
+        let google_body =
+            r#"{"error": {"code": 403, "errors": [{"reason": "dailyLimitExceeded"}]}}"#;
+        assert_eq!(
+            google_error(StatusCode::FORBIDDEN, google_body),
+            TranslationError::QuotaExceeded
+        );
+        assert_eq!(
+            deepl_error(StatusCode::from_u16(456).unwrap(), ""),
+            TranslationError::QuotaExceeded
+        );
+        assert_eq!(
+            deepl_error(StatusCode::FORBIDDEN, r#"{"message": "Wrong endpoint"}"#),
+            TranslationError::InvalidKey
+        );
+        assert_eq!(
+            libre_error(
+                StatusCode::BAD_REQUEST,
+                r#"{"error": "xx is not supported"}"#
+            ),
+            TranslationError::UnsupportedLanguage("xx is not supported".to_string())
+        );
+        assert_eq!(
+            libre_error(StatusCode::TOO_MANY_REQUESTS, "Slowdown"),
+            TranslationError::RateLimited
+        );
+        assert_eq!(
+            libre_error(StatusCode::GATEWAY_TIMEOUT, ""),
+            TranslationError::Timeout
+        );
+        assert!(matches!(
+            libre_error(StatusCode::BAD_GATEWAY, ""),
+            TranslationError::Unavailable(_)
+        ));
+    }
+
+    /// Fails with an error a number of times before translating
+    struct FlakyTranslator {
+        error: TranslationError,
+        failures: AtomicU32,
+    }
+
+    #[async_trait]
+    impl Translator for FlakyTranslator {
+        fn provider(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn translate(
+            &self,
+            text: &str,
+            _source: &SourceLanguage,
+            _target: &Language,
+        ) -> Result<Translation, Error> {
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(self.error.clone().into());
+            }
+            Ok(Translation {
+                text: text.to_string(),
+                source: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrying_translator() {
+        let translate = |error: TranslationError, failures: u32| async move {
+            RetryingTranslator::new(Arc::new(FlakyTranslator {
+                error,
+                failures: AtomicU32::new(failures),
+            }))
+            .with_backoff(Duration::ZERO)
+            .translate("hello", &SourceLanguage::Auto, &Language::default())
+            .await
+        };
+
+        assert!(translate(TranslationError::Timeout, 2).await.is_ok());
+        assert!(matches!(
+            translate(TranslationError::RateLimited, 3).await,
+            Err(Error::Translation(TranslationError::RateLimited))
+        ));
+        assert!(matches!(
+            translate(TranslationError::InvalidKey, 1).await,
+            Err(Error::Translation(TranslationError::InvalidKey))
+        ));
+    }
 }
@@ -1,8 +1,16 @@
-use chrono::{Duration, NaiveDateTime};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use sqlx::FromRow;
-use validator::Validate;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::types::PgInterval;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, Row};
+use validator::{Validate, ValidateUrl, ValidationError};
+
+use crate::scheduler::{SchedulerKind, SchedulingState, INITIAL_EASE_FACTOR};
+use crate::translate::Language;
 
 /// First page of a paginated query
 /// This is the default page number to start with
@@ -35,20 +43,63 @@ pub const MAX_DEFINITION_LENGTH: u64 = 5000;
 /// This is the maximum length of a URL for a word
 pub const MAX_URL_LENGTH: u64 = 5000;
 
+/// Maximum length of the sentence a word was found in
+pub const MAX_CONTEXT_LENGTH: u64 = 5000;
+
+/// Maximum length of a part of speech
+pub const MAX_PART_OF_SPEECH_LENGTH: u64 = 32;
+
+/// Maximum number of senses of a word, and of examples of a sense
+pub const MAX_SENSES: u64 = 20;
+
+/// Maximum length of the name of a tag or deck
+pub const MAX_NAME_LENGTH: u64 = 64;
+
+/// Maximum number of tags of a word
+pub const MAX_TAGS: u64 = 20;
+
+/// Default number of new words introduced per day
+pub const DEFAULT_NEW_WORDS_PER_DAY: i32 = 20;
+
+/// Default number of reviews of known words per day
+pub const DEFAULT_MAX_REVIEWS_PER_DAY: i32 = 200;
+
+/// Whether English inflections count as the same word by default
+pub const DEFAULT_LEMMATIZE: bool = false;
+
+/// Maximum value of a daily limit
+pub const MAX_DAILY_LIMIT: i32 = 9999;
+
+/// Maximum length of a search query
+pub const MAX_SEARCH_QUERY_LENGTH: u64 = 200;
+
 /// Minimum length of a user ID
 pub const MIN_USER_ID_LENGTH: usize = 5;
 
 /// Maximum length of a user ID
-pub const MAX_USER_ID_LENGTH: usize = 50;
+pub const MAX_USER_ID_LENGTH: usize = 255;
 
+/// A username, a UUID `sub` or an email address, depending on the claim of the
+/// token users are identified by
+///
+/// Besides word characters, `.`, `@`, `+` and `-` are allowed so that `sub`
+/// and `email` claims can be used as they are. Whitespace, slashes, quotes and
+/// other characters are still rejected.
 pub static USER_ID_PATTERN: Lazy<Regex> = Lazy::new(|| {
     Regex::new(&format!(
-        r"^[a-zA-Z0-9_]{{{},{}}}$",
+        r"^[a-zA-Z0-9_.@+-]{{{},{}}}$",
         MIN_USER_ID_LENGTH, MAX_USER_ID_LENGTH
     ))
     .unwrap()
 });
 
+/// Maximum length of a domain name
+pub const MAX_DOMAIN_LENGTH: u64 = 253;
+
+pub static DOMAIN_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?i)[a-z0-9]([a-z0-9-]*[a-z0-9])?(\.[a-z0-9]([a-z0-9-]*[a-z0-9])?)*$").unwrap()
+});
+
 /// Represents a word entry in the database
 #[derive(Debug, FromRow)]
 pub struct Word {
@@ -57,8 +108,17 @@ pub struct Word {
     pub word: String,
     pub definition: String,
     pub url: String,
+    pub context: Option<String>, // The sentence the word was found in
+    pub part_of_speech: Option<String>, // Such as noun, verb or adj
+    #[sqlx(json)]
+    pub senses: Vec<WordSense>,
+    pub urls: Vec<String>, // Every page the word was saved from, oldest first
+    pub deck_id: Option<i32>,
+    pub tags: Vec<String>, // Names of the tags, alphabetically
     pub date_added: NaiveDateTime,
     pub initial_forgetting_rate: f64,
+    pub retention: f64, // Predicted probability of recalling the word now
+    pub next_review_date: Option<NaiveDateTime>,
 }
 
 /// Represents a new word entry to be inserted into the database
@@ -70,10 +130,19 @@ pub struct NewWord {
     pub word: String,
     #[validate(length(min = 1, max = MAX_DEFINITION_LENGTH))]
     pub definition: String,
-    #[validate(url)]
-    pub url: String,
+    #[validate(custom(function = "validate_page_url"))]
+    pub url: String, // Empty if the word was not saved from a page, such as imported or typed in words
     #[validate(range(min = 0.0, max = 1.0))]
     pub initial_forgetting_rate: Option<f64>,
+    #[validate(length(min = 1, max = MAX_CONTEXT_LENGTH))]
+    pub context: Option<String>,
+    #[validate(length(min = 1, max = MAX_PART_OF_SPEECH_LENGTH))]
+    pub part_of_speech: Option<String>,
+    #[validate(length(max = MAX_SENSES), nested)]
+    pub senses: Vec<WordSense>,
+    pub deck_id: Option<i32>,
+    #[validate(length(max = MAX_TAGS), custom(function = "validate_names"))]
+    pub tags: Vec<String>, // Names of the tags, created if the user has none by that name
 }
 
 impl NewWord {
@@ -84,6 +153,11 @@ impl NewWord {
             definition,
             url,
             initial_forgetting_rate: None,
+            context: None,
+            part_of_speech: None,
+            senses: Vec::new(),
+            deck_id: None,
+            tags: Vec::new(),
         }
     }
 
@@ -91,17 +165,302 @@ impl NewWord {
         self.initial_forgetting_rate = Some(rate);
         self
     }
+
+    pub fn with_context(mut self, context: String) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    pub fn with_part_of_speech(mut self, part_of_speech: String) -> Self {
+        self.part_of_speech = Some(part_of_speech);
+        self
+    }
+
+    pub fn with_senses(mut self, senses: Vec<WordSense>) -> Self {
+        self.senses = senses;
+        self
+    }
+
+    pub fn with_deck(mut self, deck_id: i32) -> Self {
+        self.deck_id = Some(deck_id);
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
 }
 
-/// Represents a review session for a word
+/// Represents one meaning of a word, with sentences using it that way
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct WordSense {
+    #[validate(length(min = 1, max = MAX_DEFINITION_LENGTH))]
+    pub definition: String,
+    #[validate(length(max = MAX_SENSES), custom(function = "validate_examples"))]
+    #[serde(default)]
+    pub examples: Vec<String>,
+}
+
+fn validate_examples(examples: &[String]) -> Result<(), ValidationError> {
+    if examples
+        .iter()
+        .any(|e| e.is_empty() || e.chars().count() as u64 > MAX_CONTEXT_LENGTH)
+    {
+        return Err(ValidationError::new(
+            "Examples must be between 1 and 5000 characters",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_page_url(url: &str) -> Result<(), ValidationError> {
+    if !url.is_empty() && !url.validate_url() {
+        return Err(ValidationError::new("url"));
+    }
+    Ok(())
+}
+
+fn validate_names(names: &[String]) -> Result<(), ValidationError> {
+    if names.iter().any(|name| !is_valid_name(name)) {
+        return Err(ValidationError::new(
+            "Names must be between 1 and 64 characters",
+        ));
+    }
+    Ok(())
+}
+
+/// Whether a name can be given to a tag or deck, surrounding whitespace aside
+pub fn is_valid_name(name: &str) -> bool {
+    let length = name.trim().chars().count() as u64;
+    (1..=MAX_NAME_LENGTH).contains(&length)
+}
+
+/// The field a list of words is sorted by
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordSort {
+    Alphabetical,
+    #[default]
+    DateAdded,
+    NextReviewDate,
+    Retention,
+}
+
+impl WordSort {
+    /// The order used when none is given, the most useful end of the list first
+    pub fn default_order(&self) -> SortOrder {
+        match self {
+            Self::DateAdded => SortOrder::Desc,
+            Self::Alphabetical | Self::NextReviewDate | Self::Retention => SortOrder::Asc,
+        }
+    }
+}
+
+/// The direction of a sort
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Represents the criteria for listing words, every filter left as `None` matches all words
+#[derive(Debug, Default, Validate)]
+pub struct WordFilter {
+    pub sort: WordSort,
+    pub order: Option<SortOrder>,
+    pub added_from: Option<NaiveDate>, // Inclusive
+    pub added_to: Option<NaiveDate>,   // Inclusive
+    #[validate(length(max = MAX_DOMAIN_LENGTH), regex(path = *DOMAIN_PATTERN))]
+    pub domain: Option<String>, // Matches the host of the URL and its subdomains
+    pub due: Option<bool>,
+    #[validate(length(min = 1, max = MAX_NAME_LENGTH))]
+    pub tag: Option<String>, // Name of a tag, ignoring case
+    pub deck_id: Option<i32>,
+}
+
+/// Represents the outcome of adding a word
+#[derive(Debug)]
+pub struct InsertedWord {
+    pub word: Word,
+    pub merged: bool, // The word already existed and the new one was merged into it
+}
+
+/// Represents a label a user can attach to any number of words
+#[derive(Debug, FromRow)]
+pub struct Tag {
+    pub tag_id: i32,
+    pub user_id: String, // Matches the JWT 'username' field
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub word_count: i64,
+}
+
+/// Represents a collection of words, each word belongs to at most one deck
 #[derive(Debug, FromRow)]
+pub struct Deck {
+    pub deck_id: i32,
+    pub user_id: String, // Matches the JWT 'username' field
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub word_count: i64,
+}
+
+/// Represents what was erased when a user deleted their account
+#[derive(Debug)]
+pub struct DeletedUserData {
+    pub user_id: String,
+    pub words: u64,
+    pub review_sessions: u64,
+    pub forgetting_curves: u64,
+    pub tags: u64,
+    pub decks: u64,
+    pub settings: bool, // Whether the user had changed their settings
+    pub access_tokens: u64,
+    pub deleted_at: NaiveDateTime,
+}
+
+/// Represents one page of a list of words
+#[derive(Debug)]
+pub struct WordPage {
+    pub words: Vec<Word>,
+    pub next_cursor: Option<String>, // Opaque cursor of the next page, `None` on the last page
+    pub total: i64,                  // Number of words matching the filter across all pages
+}
+
+/// Position in a list of words where the next page starts
+///
+/// Sorted lists continue after the sort key and ID of the last word, so words
+/// added while paging do not shift the following pages. Search results are
+/// ranked by relevance and continue from an offset, as do lists sorted by
+/// retention, which decays between requests and would skip words past a key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WordCursor {
+    Keyset {
+        sort: WordSort,
+        order: SortOrder,
+        key: CursorKey,
+        word_id: i32,
+    },
+    Offset(u64),
+}
+
+/// The sort key of the last word of a page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CursorKey {
+    Text(String),
+    Time(Option<NaiveDateTime>),
+}
+
+impl WordCursor {
+    /// The cursor continuing after a word in a sorted list, `None` for sorts
+    /// that continue from an offset
+    pub fn after(word: &Word, sort: WordSort, order: SortOrder) -> Option<Self> {
+        let key = match sort {
+            WordSort::Alphabetical => CursorKey::Text(word.word.clone()),
+            WordSort::DateAdded => CursorKey::Time(Some(word.date_added)),
+            WordSort::NextReviewDate => CursorKey::Time(word.next_review_date),
+            WordSort::Retention => return None,
+        };
+        Some(Self::Keyset {
+            sort,
+            order,
+            key,
+            word_id: word.word_id,
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Decodes a cursor, returning `None` if it was not produced by `encode`
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// Represents changes to a word, fields left as `None` are not changed
+///
+/// Optional fields set to `Some(None)` are cleared, and an empty URL leaves the
+/// word without a page.
+#[derive(Debug, Default, Validate)]
+pub struct UpdateWord {
+    #[validate(length(min = 1, max = MAX_WORD_LENGTH))]
+    pub word: Option<String>,
+    #[validate(length(min = 1, max = MAX_DEFINITION_LENGTH))]
+    pub definition: Option<String>,
+    #[validate(custom(function = "validate_page_url"))]
+    pub url: Option<String>, // Replaces the page the word was first saved from among its URLs
+    #[validate(length(min = 1, max = MAX_CONTEXT_LENGTH))]
+    pub context: Option<Option<String>>,
+    #[validate(length(min = 1, max = MAX_PART_OF_SPEECH_LENGTH))]
+    pub part_of_speech: Option<Option<String>>,
+    #[validate(length(max = MAX_SENSES), nested)]
+    pub senses: Option<Vec<WordSense>>,
+    pub deck_id: Option<Option<i32>>,
+    #[validate(length(max = MAX_TAGS), custom(function = "validate_names"))]
+    pub tags: Option<Vec<String>>, // Replaces all tags of the word
+}
+
+/// Represents a review session for a word
+#[derive(Debug)]
 pub struct ReviewSession {
     pub session_id: i32,
     pub word_id: i32, // Foreign key from the words table
     pub review_date: NaiveDateTime,
-    pub recall_score: i32, // Scale from 1 to 5
+    pub recall_score: Option<i32>, // Scale from 1 to 5, missing for the session created with the word
     pub time_to_forget: Option<Duration>,
     pub next_review_date: Option<NaiveDateTime>,
+    pub repetitions: i32,
+    pub ease_factor: f64,
+    pub stability: f64,
+    pub difficulty: f64,
+}
+
+impl ReviewSession {
+    /// The scheduling state of the word after this session
+    pub fn scheduling_state(&self) -> SchedulingState {
+        SchedulingState {
+            repetitions: self.repetitions,
+            ease_factor: self.ease_factor,
+            stability: self.stability,
+            difficulty: self.difficulty,
+            interval: self
+                .next_review_date
+                .map(|next_review_date| next_review_date - self.review_date)
+                .unwrap_or_else(Duration::zero),
+        }
+    }
+}
+
+/// sqlx can encode a `chrono::Duration` as an `INTERVAL` but not decode one,
+/// so the interval columns are read as `PgInterval` and converted by hand
+impl FromRow<'_, PgRow> for ReviewSession {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            session_id: row.try_get("session_id")?,
+            word_id: row.try_get("word_id")?,
+            review_date: row.try_get("review_date")?,
+            recall_score: row.try_get("recall_score")?,
+            time_to_forget: row
+                .try_get::<Option<PgInterval>, _>("time_to_forget")?
+                .map(interval_to_duration),
+            next_review_date: row.try_get("next_review_date")?,
+            repetitions: row.try_get("repetitions")?,
+            ease_factor: row.try_get("ease_factor")?,
+            stability: row.try_get("stability")?,
+            difficulty: row.try_get("difficulty")?,
+        })
+    }
+}
+
+/// Converts a Postgres interval to a duration, counting a month as 30 days
+pub(crate) fn interval_to_duration(interval: PgInterval) -> Duration {
+    Duration::days(interval.months as i64 * 30 + interval.days as i64)
+        + Duration::microseconds(interval.microseconds)
 }
 
 /// Represents a new review session entry to be inserted into the database
@@ -111,8 +470,17 @@ pub struct NewReviewSession {
     pub word_id: i32,
     #[validate(range(min = 1, max = 5))]
     pub recall_score: i32,
+    pub review_date: NaiveDateTime, // Defaults to now
     pub time_to_forget: Option<Duration>,
     pub next_review_date: Option<NaiveDateTime>,
+    #[validate(range(min = 0))]
+    pub repetitions: i32,
+    #[validate(range(min = 0.0))]
+    pub ease_factor: f64,
+    #[validate(range(min = 0.0))]
+    pub stability: f64,
+    #[validate(range(min = 0.0, max = 10.0))]
+    pub difficulty: f64,
 }
 
 impl NewReviewSession {
@@ -120,13 +488,27 @@ impl NewReviewSession {
         Self {
             word_id,
             recall_score,
+            review_date: Utc::now().naive_utc(),
             time_to_forget: None,
             next_review_date: None,
+            repetitions: 0,
+            ease_factor: INITIAL_EASE_FACTOR,
+            stability: 0.0,
+            difficulty: 0.0,
         }
     }
 
-    pub fn with_time_to_forget(mut self, time_to_forget: Duration) -> Self {
-        self.time_to_forget = Some(time_to_forget);
+    pub fn with_scheduling_state(mut self, state: SchedulingState) -> Self {
+        self.repetitions = state.repetitions;
+        self.ease_factor = state.ease_factor;
+        self.stability = state.stability;
+        self.difficulty = state.difficulty;
+        self.time_to_forget = Some(state.interval);
+        self
+    }
+
+    pub fn with_review_date(mut self, review_date: NaiveDateTime) -> Self {
+        self.review_date = review_date;
         self
     }
 
@@ -137,7 +519,7 @@ impl NewReviewSession {
 }
 
 /// Represents the forgetting curve for a word
-#[derive(Debug, FromRow)]
+#[derive(Debug)]
 pub struct ForgettingCurve {
     pub curve_id: i32,
     pub word_id: i32, // Foreign key from the words table
@@ -146,6 +528,20 @@ pub struct ForgettingCurve {
     pub review_count: i32,   // Number of reviews done
 }
 
+impl FromRow<'_, PgRow> for ForgettingCurve {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            curve_id: row.try_get("curve_id")?,
+            word_id: row.try_get("word_id")?,
+            review_interval: row
+                .try_get::<Option<PgInterval>, _>("review_interval")?
+                .map(interval_to_duration),
+            retention_rate: row.try_get("retention_rate")?,
+            review_count: row.try_get("review_count")?,
+        })
+    }
+}
+
 /// Represents a new forgetting curve entry to be inserted into the database
 #[derive(Debug, Validate)]
 pub struct NewForgettingCurve {
@@ -155,4 +551,93 @@ pub struct NewForgettingCurve {
     pub retention_rate: f64,
     #[validate(range(min = 1))]
     pub review_count: i32,
+    pub review_interval: Option<Duration>,
+}
+
+impl NewForgettingCurve {
+    pub fn new(word_id: i32, retention_rate: f64, review_count: i32) -> Self {
+        Self {
+            word_id,
+            retention_rate,
+            review_count,
+            review_interval: None,
+        }
+    }
+
+    pub fn with_review_interval(mut self, review_interval: Duration) -> Self {
+        self.review_interval = Some(review_interval);
+        self
+    }
+}
+
+/// Represents the preferences of a user
+#[derive(Debug, Validate, FromRow)]
+pub struct UserSettings {
+    #[validate(regex(path = *USER_ID_PATTERN))]
+    pub user_id: String,
+    #[sqlx(try_from = "String")]
+    pub scheduler: SchedulerKind,
+    #[validate(range(min = 0, max = MAX_DAILY_LIMIT))]
+    pub new_words_per_day: i32,
+    #[validate(range(min = 0, max = MAX_DAILY_LIMIT))]
+    pub max_reviews_per_day: i32,
+    #[sqlx(try_from = "String")]
+    pub target_language: Language,
+    pub lemmatize: bool, // Whether English inflections count as the same word
+}
+
+impl UserSettings {
+    /// The settings of a user who has not changed anything yet
+    pub fn new(user_id: String) -> Self {
+        Self {
+            user_id,
+            scheduler: SchedulerKind::default(),
+            new_words_per_day: DEFAULT_NEW_WORDS_PER_DAY,
+            max_reviews_per_day: DEFAULT_MAX_REVIEWS_PER_DAY,
+            target_language: Language::default(),
+            lemmatize: DEFAULT_LEMMATIZE,
+        }
+    }
+}
+
+/// Represents the words a user should review now
+#[derive(Debug)]
+pub struct ReviewQueue {
+    pub words: Vec<Word>,
+    pub due_count: i64, // Reviewed words that are due, regardless of the daily limit
+    pub new_count: i64, // Words that have never been reviewed
+    pub reviews_remaining: i64, // Reviews left under today's limit
+    pub new_remaining: i64, // New words left under today's limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_id_pattern() {
+        for user_id in [
+            "test_user",
+            "jane.doe+words@example.com",
+            "3f8b2c1e-9d4a-4b6e-8c2f-1a2b3c4d5e6f",
+        ] {
+            assert!(USER_ID_PATTERN.is_match(user_id), "{user_id}");
+        }
+
+        for user_id in [
+            "",
+            "abcd",
+            &"a".repeat(MAX_USER_ID_LENGTH + 1),
+            "jane doe",
+            "../../etc",
+            "jane%40example.com",
+            "jane'--",
+            "jane\"doe\"",
+            "jane;drop",
+            "jäne_doe",
+            "jane_doe\n",
+        ] {
+            assert!(!USER_ID_PATTERN.is_match(user_id), "{user_id}");
+        }
+    }
 }